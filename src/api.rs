@@ -1,9 +1,14 @@
 use std::collections::HashMap;
 use std::io::Write;
+use std::str::FromStr;
 
 use chrono::NaiveDate;
 
-use crate::ChangeLogConfig;
+use crate::exports::items_by_type;
+use crate::imports::change_type::Confidence;
+use crate::layout::MarkdownLayout;
+pub use crate::reference::Reference;
+use crate::{ChangeLogConfig, ChgError};
 
 /// Entire changelog.
 pub struct ChangeLog {
@@ -16,9 +21,11 @@ pub struct ChangeLog {
 }
 
 /// Container of changes related to one version, either released or unreleased.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ChangeSet {
     pub items: Vec<ChangeItem>,
+    /// free text preceding the items, like message of the release tag
+    pub notes: String,
 }
 
 #[derive(Debug, Clone)]
@@ -29,6 +36,8 @@ pub struct ReleaseHeader {
     pub timestamp: NaiveDate,
     /// yanked are versions that we had to unpublish, usually due to a significant problem found after release
     pub yanked: bool,
+    /// maintenance branch of the release; empty for releases on the main line
+    pub branch: String,
 }
 
 impl ReleaseHeader {
@@ -38,6 +47,7 @@ impl ReleaseHeader {
             tag: tag.to_string(),
             timestamp,
             yanked,
+            branch: "".to_string(),
         }
     }
 
@@ -57,23 +67,126 @@ impl ReleaseHeader {
                 tag: tag.to_string(),
                 timestamp,
                 yanked,
+                branch: "".to_string(),
             })
         }
     }
 }
 
+/// Outcome of [`ChangeLog::sync_from`].
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// items added, with version of the target section (`None` for Unreleased)
+    pub added: Vec<(Option<String>, ChangeItem)>,
+    /// items moved from Unreleased into a release, with its version
+    pub moved: Vec<(String, ChangeItem)>,
+    /// versions of newly created release sections
+    pub releases_created: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+    /// items not added because they are on the ignore list
+    pub ignored: Vec<ChangeItem>,
+    /// refs added to the ignore list, because their items were deleted since last sync
+    pub newly_ignored: Vec<String>,
+    /// true if the sync state kept in config has changed and should be stored
+    pub state_changed: bool,
+}
+
+/// Disagreement between the changelog and imported content; the changelog always wins.
+#[derive(Debug, Clone)]
+pub enum SyncConflict {
+    /// new item refers to something already listed in an existing release; it is not added
+    AlreadyReleased {
+        item: ChangeItem,
+        reference: Reference,
+        version: String,
+    },
+    /// new item is similar to several existing items, so it is unclear whether it is already present; it is not added
+    AmbiguousMatch {
+        item: ChangeItem,
+        candidates: Vec<ChangeItem>,
+    },
+    /// the same release is dated differently on both sides
+    DateMismatch {
+        version: String,
+        old: NaiveDate,
+        new: NaiveDate,
+    },
+}
+
+impl SyncReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.moved.is_empty()
+            && self.releases_created.is_empty()
+            && self.conflicts.is_empty()
+            && self.ignored.is_empty()
+            && !self.state_changed
+    }
+
+    /// Added items whose change type was only guessed, and needs review.
+    pub fn guessed_types(&self) -> Vec<&(Option<String>, ChangeItem)> {
+        self.added
+            .iter()
+            .filter(|(_, item)| item.type_confidence == Some(Confidence::Low))
+            .collect()
+    }
+}
+
 /// One change it the release.
 #[derive(Clone, Debug)]
 pub struct ChangeItem {
-    pub refs: Vec<String>,
+    pub refs: Vec<Reference>,
     pub change_type: ChangeType,
     pub component: String,
     pub text: String,
     pub authors: Vec<String>,
+    /// id of the commit this item was imported from
+    pub commit: Option<String>,
+    /// more detailed description, rendered as indented paragraph under the item
+    pub details: String,
+    /// how sure the git import is about `change_type`; `None` when read from a changelog
+    pub type_confidence: Option<Confidence>,
+}
+
+/// Length of abbreviated commit ids, as shown in the changelog
+pub const SHORT_COMMIT_LEN: usize = 7;
+
+/// Commit ids match even if one of them is abbreviated.
+pub(crate) fn same_commit(a: &str, b: &str) -> bool {
+    a.len() >= SHORT_COMMIT_LEN
+        && b.len() >= SHORT_COMMIT_LEN
+        && (a.starts_with(b) || b.starts_with(a))
+}
+
+impl ChangeItem {
+    /// Abbreviated id of the source commit.
+    pub fn short_commit(&self) -> Option<&str> {
+        self.commit
+            .as_ref()
+            .map(|c| &c[0..c.len().min(SHORT_COMMIT_LEN)])
+    }
+
+    /// Everything that identifies the item: refs and abbreviated commit id.
+    pub fn keys(&self) -> Vec<Reference> {
+        let mut keys = self.refs.clone();
+        if let Some(commit) = self.short_commit() {
+            keys.push(Reference::Commit(commit.to_string()));
+        }
+        keys
+    }
+
+    /// True if the key is one of refs, or the commit id (possibly abbreviated).
+    pub fn has_key(&self, key: &Reference) -> bool {
+        self.refs.iter().any(|r| r == key)
+            || match (key, &self.commit) {
+                (Reference::Commit(key), Some(commit)) => same_commit(commit, key),
+                _ => false,
+            }
+    }
 }
 
 /// Type of the change
-#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+#[derive(Eq, PartialEq, Hash, Debug, Copy, Clone)]
 pub enum ChangeType {
     Other,
     Added,
@@ -84,42 +197,136 @@ pub enum ChangeType {
     Refactored,
 }
 
+impl ChangeType {
+    pub const ALL: [ChangeType; 7] = [
+        ChangeType::Added,
+        ChangeType::Changed,
+        ChangeType::Deprecated,
+        ChangeType::Removed,
+        ChangeType::Fixed,
+        ChangeType::Refactored,
+        ChangeType::Other,
+    ];
+
+    /// Lowercase name, as used in config files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeType::Other => "other",
+            ChangeType::Added => "added",
+            ChangeType::Fixed => "fixed",
+            ChangeType::Changed => "changed",
+            ChangeType::Deprecated => "deprecated",
+            ChangeType::Removed => "removed",
+            ChangeType::Refactored => "refactored",
+        }
+    }
+
+    /// Capitalized name, as used in headings
+    pub fn title(&self) -> &'static str {
+        match self {
+            ChangeType::Other => "Other",
+            ChangeType::Added => "Added",
+            ChangeType::Fixed => "Fixed",
+            ChangeType::Changed => "Changed",
+            ChangeType::Deprecated => "Deprecated",
+            ChangeType::Removed => "Removed",
+            ChangeType::Refactored => "Refactored",
+        }
+    }
+}
+
+impl FromStr for ChangeType {
+    type Err = ChgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        ChangeType::ALL
+            .iter()
+            .find(|t| t.as_str() == s)
+            .copied()
+            .ok_or(ChgError::UnknownChangeType(s))
+    }
+}
+
 impl ChangeLog {
+    /// Renders the changelog as markdown, per `[templates]` config.
     pub fn print_markdown(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let layout = MarkdownLayout::from_config(&self.config)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+        let prolog = self.prolog.trim();
+        if !prolog.is_empty() {
+            writeln!(out, "{}", prolog)?;
+            writeln!(out)?;
+        }
         if let Some(unreleased) = &self.unreleased {
             writeln!(out, "## Unreleased")?;
-            Self::print_markdown_items(out, &unreleased)?;
+            self.print_markdown_items(out, &unreleased, &layout)?;
         }
 
         for (ver, release) in &self.releases {
-            let ts = ver.timestamp.to_string();
-            writeln!(
-                out,
-                "## {} - {}{}",
-                ver.version,
-                &ts[0..10],
-                if ver.yanked { " [YANKED]" } else { "" }
-            )?;
-            Self::print_markdown_items(out, &release)?;
+            writeln!(out, "{}", layout.render_heading(ver))?;
+            self.print_markdown_items(out, &release, &layout)?;
+        }
+        let footer = layout.render_footer(&self.releases);
+        if !footer.is_empty() {
+            // empty sections end without a blank line
+            if let Some((_, last)) = self.releases.last() {
+                if last.items.is_empty() && last.notes.is_empty() {
+                    writeln!(out)?;
+                }
+            }
+            for line in footer {
+                writeln!(out, "{}", line)?;
+            }
+            writeln!(out)?;
+        }
+        let epilog = self.epilog.trim();
+        if !epilog.is_empty() {
+            writeln!(out, "{}", epilog)?;
         }
         Ok(())
     }
 
-    fn print_markdown_items(out: &mut dyn Write, changes: &ChangeSet) -> std::io::Result<()> {
-        if !changes.items.is_empty() {
+    fn print_markdown_items(
+        &self,
+        out: &mut dyn Write,
+        changes: &ChangeSet,
+        layout: &MarkdownLayout,
+    ) -> std::io::Result<()> {
+        if !changes.notes.is_empty() {
             writeln!(out)?;
-            for item in &changes.items {
-                write!(out, "- ")?;
-                if !item.refs.is_empty() {
-                    write!(out, "{}: ", item.refs.join(", "))?;
+            for line in changes.notes.lines() {
+                if line.is_empty() {
+                    writeln!(out, ">")?;
+                } else {
+                    writeln!(out, "> {}", line)?;
                 }
-                if !item.component.is_empty() {
-                    write!(out, "[{}] ", item.component)?;
+            }
+            if changes.items.is_empty() {
+                writeln!(out)?;
+            }
+        }
+        if changes.items.is_empty() {
+            return Ok(());
+        }
+        if layout.is_grouped() {
+            for (change_type, items) in items_by_type(changes) {
+                writeln!(out)?;
+                if let Some(heading) = layout.render_type_heading(change_type) {
+                    writeln!(out, "{}", heading)?;
+                }
+                writeln!(out)?;
+                for item in items {
+                    writeln!(out, "{}", layout.render_item(item, &self.config))?;
                 }
-                writeln!(out, "{} / {}", item.text, item.authors.join(", "))?;
             }
+        } else {
             writeln!(out)?;
+            for item in &changes.items {
+                writeln!(out, "{}", layout.render_item(item, &self.config))?;
+            }
         }
+        writeln!(out)?;
         Ok(())
     }
 }
@@ -0,0 +1,17 @@
+use std::path::PathBuf;
+
+use changelog::discovery::ConfigLayers;
+use changelog::ChgError;
+
+/// Prints the effective config as TOML keys, each commented with where the value comes from.
+pub fn cmd_config_show(changelog_file: &PathBuf, dir: &PathBuf) -> Result<(), ChgError> {
+    let mut layers = ConfigLayers::discover(dir)?;
+    if changelog_file.exists() {
+        let text = std::fs::read_to_string(changelog_file)?;
+        layers = layers.with_embedded(changelog_file, &text)?;
+    }
+    for (key, value, source) in layers.provenance()? {
+        println!("{} = {}  # {}", key, value, source);
+    }
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use changelog::exports::ExportFormat;
+use changelog::{ChangeLog, ChgError};
+
+/// Renders the changelog in another format, to a file or to stdout.
+/// With `spec`, the `%changelog` section of that RPM spec file is replaced in place instead.
+pub fn cmd_export(
+    changelog_file: &PathBuf,
+    dir: &PathBuf,
+    format: ExportFormat,
+    fragment: bool,
+    output: Option<&PathBuf>,
+    spec: Option<&PathBuf>,
+) -> Result<(), ChgError> {
+    let changelog = ChangeLog::load(changelog_file, dir)?;
+    if let Some(spec) = spec {
+        let spec_text = std::fs::read_to_string(spec)?;
+        std::fs::write(spec, changelog.replace_rpm_changelog(&spec_text)?)?;
+        info!("Updated %changelog in {}", spec.display());
+        return Ok(());
+    }
+    let mut out: Box<dyn Write> = match output {
+        None => Box::new(std::io::stdout()),
+        Some(output) => Box::new(File::create(output)?),
+    };
+    format.renderer(fragment).render(&changelog, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
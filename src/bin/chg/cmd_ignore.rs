@@ -0,0 +1,25 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use changelog::{ChangeLog, ChgError};
+
+/// Put a ref on the ignore list, so that `sync` never adds it again, and remove its unreleased items.
+pub fn cmd_ignore(
+    changelog_file: &PathBuf,
+    dir: &PathBuf,
+    references: &[String],
+) -> Result<(), ChgError> {
+    let mut changelog = ChangeLog::load(changelog_file, dir)?;
+    for reference in references {
+        for item in changelog.ignore(reference) {
+            info!("Removed: {}", item.text);
+        }
+        info!("Ignoring {}", reference);
+    }
+    changelog.store_config()?;
+    let mut file = File::create(changelog_file)?;
+    changelog.print_markdown(&mut file)?;
+    file.flush()?;
+    Ok(())
+}
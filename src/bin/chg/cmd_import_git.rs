@@ -1,4 +1,6 @@
-use changelog::{ChangeLog, ChangeLogConfig};
+use changelog::discovery::ConfigLayers;
+use changelog::imports::from_git_repo::GitImportOptions;
+use changelog::{ChangeLog, ChgError};
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
@@ -6,10 +8,10 @@ use std::path::PathBuf;
 pub fn cmd_import_git(
     changelog_file: &PathBuf,
     dir: &PathBuf,
-    stop_version: Option<String>,
-) -> std::io::Result<()> {
-    let config = ChangeLogConfig::default();
-    let changelog = ChangeLog::import_git_commits(dir, stop_version, &config);
+    options: &GitImportOptions,
+) -> Result<(), ChgError> {
+    let config = ConfigLayers::discover(dir)?.config()?;
+    let changelog = ChangeLog::import_git(dir, options, &config)?;
     let mut file = File::create(changelog_file)?;
     changelog.print_markdown(&mut file)?;
     // changelog.to_markdown(&mut std::io::stdout())
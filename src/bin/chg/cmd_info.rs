@@ -1,10 +1,9 @@
-use changelog::api::ChangeSet;
+use changelog::api::{ChangeSet, Reference};
 use changelog::{ChangeLog, ChgError};
 use std::path::PathBuf;
 
-pub fn cmd_info(changelog_file: &PathBuf) -> Result<(), ChgError> {
-    let text = std::fs::read_to_string(changelog_file)?;
-    let changelog = ChangeLog::import_markdown(&text)?;
+pub fn cmd_info(changelog_file: &PathBuf, dir: &PathBuf) -> Result<(), ChgError> {
+    let changelog = ChangeLog::load(changelog_file, dir)?;
     if let Some(unreleased) = changelog.unreleased {
         println!("Unreleased");
         print_changeset(&unreleased);
@@ -24,6 +23,6 @@ pub fn cmd_info(changelog_file: &PathBuf) -> Result<(), ChgError> {
 
 fn print_changeset(changeset: &ChangeSet) {
     for item in &changeset.items {
-        println!("* Refs:{:?}, '{}'", item.refs, item.text)
+        println!("* Refs:{}, '{}'", Reference::join(&item.refs), item.text)
     }
 }
@@ -0,0 +1,17 @@
+use std::path::PathBuf;
+
+use changelog::{ChangeLog, ChgError};
+
+/// Checks changelog items per `[lint]` config; fails if any item needs fixing.
+pub fn cmd_lint(changelog_file: &PathBuf, dir: &PathBuf, all: bool) -> Result<(), ChgError> {
+    let changelog = ChangeLog::load(changelog_file, dir)?;
+    let issues = changelog.lint(all)?;
+    for issue in &issues {
+        warn!("{}", issue);
+    }
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(ChgError::LintFailed(issues.len()))
+    }
+}
@@ -0,0 +1,23 @@
+use std::path::PathBuf;
+
+use changelog::exports::ExportFormat;
+use changelog::{ChangeLog, ChgError};
+
+use crate::cmd_sync::sync_and_store;
+
+/// Merge history from a changelog in another format, like `debian/changelog`, into `CHANGELOG.md`.
+pub fn cmd_merge(
+    changelog_file: &PathBuf,
+    dir: &PathBuf,
+    format: ExportFormat,
+    source: &PathBuf,
+) -> Result<(), ChgError> {
+    let changelog = ChangeLog::load(changelog_file, dir)?;
+    let source_text = std::fs::read_to_string(source)?;
+    let other = match format {
+        ExportFormat::Markdown => ChangeLog::import_markdown(&source_text)?,
+        ExportFormat::Debian => ChangeLog::import_debian(&source_text, changelog.config.clone())?,
+        _ => return Err(ChgError::UnsupportedImportFormat(format!("{:?}", format))),
+    };
+    sync_and_store(changelog_file, changelog, &other)
+}
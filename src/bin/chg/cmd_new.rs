@@ -2,20 +2,24 @@ use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 
-use changelog::builder::ChangeLogBuilder;
-use changelog::ChangeLogConfig;
+use changelog::bootstrap::LayoutStyle;
+use changelog::discovery::ConfigLayers;
+use changelog::{ChangeLog, ChgError};
 
-pub fn cmd_new(changelog_file: &PathBuf) -> std::io::Result<()> {
+/// Creates a changelog with standard prolog and config pre-filled from the repository.
+pub fn cmd_new(
+    changelog_file: &PathBuf,
+    dir: &PathBuf,
+    style: LayoutStyle,
+) -> Result<(), ChgError> {
     if changelog_file.exists() {
-        Err(std::io::Error::new(
+        return Err(ChgError::from(std::io::Error::new(
             std::io::ErrorKind::AlreadyExists,
             format!("Changelog already exists in {}", changelog_file.display()),
-        ))?;
+        )));
     }
-    let config = ChangeLogConfig::default();
-    let mut builder = ChangeLogBuilder::new(config);
-    builder.section(None);
-    let changelog = builder.build();
+    let config = ConfigLayers::discover(dir)?.config()?;
+    let changelog = ChangeLog::bootstrap(dir, config, style)?;
     let mut file = File::create(changelog_file)?;
     changelog.print_markdown(&mut file)?;
     // changelog.to_markdown(&mut std::io::stdout())
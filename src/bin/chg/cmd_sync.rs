@@ -1,17 +1,70 @@
+use std::fs::File;
+use std::io::Write;
 use std::path::PathBuf;
 
+use changelog::api::SyncConflict;
 use changelog::{ChangeLog, ChgError};
 
 /// Synchronize new commits into existing `CHANGELOG.md` file.
 /// Internally, the process is creating two instances of [`Vec<ChangeSet>`] and carefully adding stuff from one to the other
 pub fn cmd_sync(changelog_file: &PathBuf, dir: &PathBuf) -> Result<(), ChgError> {
-    let text = std::fs::read_to_string(changelog_file)?;
-    let mut changelog = ChangeLog::import_markdown(&text)?;
+    let changelog = ChangeLog::load(changelog_file, dir)?;
     let stop_version = match changelog.releases.get(0) {
         None => None,
         Some((rvs, _)) => Some(rvs.version.clone()),
     };
-    let commits = ChangeLog::import_git_commits(dir, stop_version, &changelog.config);
-    changelog.sync_from(&commits)?; //TODO
+    let commits = ChangeLog::import_git_commits(dir, stop_version, &changelog.config)?;
+    sync_and_store(changelog_file, changelog, &commits)
+}
+
+/// Syncs `new` into the changelog, reports what happened, and stores the result if anything changed.
+pub fn sync_and_store(
+    changelog_file: &PathBuf,
+    mut changelog: ChangeLog,
+    new: &ChangeLog,
+) -> Result<(), ChgError> {
+    let report = changelog.sync_from(new)?;
+    for version in &report.releases_created {
+        info!("New release: {}", version);
+    }
+    for (version, item) in &report.moved {
+        debug!("Moved to {}: {}", version, item.text);
+    }
+    for (version, item) in &report.added {
+        info!("Added to {}: {}", version.as_deref().unwrap_or("Unreleased"), item.text);
+    }
+    for item in &report.ignored {
+        debug!("Ignored: {}", item.text);
+    }
+    for reference in &report.newly_ignored {
+        info!("Deleted since last sync, will be ignored: {}", reference);
+    }
+    for conflict in &report.conflicts {
+        match conflict {
+            SyncConflict::AlreadyReleased { item, reference, version } => {
+                warn!("Not adding '{}': {} is already listed in {}", item.text, reference, version)
+            }
+            SyncConflict::AmbiguousMatch { item, candidates } => {
+                warn!("Not adding '{}': it resembles {} existing items", item.text, candidates.len());
+                for candidate in candidates {
+                    warn!("   * {}", candidate.text);
+                }
+            }
+            SyncConflict::DateMismatch { version, old, new } => {
+                warn!("Release {} is dated {} in changelog, but {} in git", version, old, new)
+            }
+        }
+    }
+    for (_, item) in report.guessed_types() {
+        warn!("Change type guessed as {}, please review: {}", item.change_type.title(), item.text);
+    }
+    if report.added.is_empty() && report.releases_created.is_empty() && !report.state_changed {
+        info!("Changelog is up to date");
+        return Ok(());
+    }
+    changelog.store_config()?;
+    let mut file = File::create(changelog_file)?;
+    changelog.print_markdown(&mut file)?;
+    file.flush()?;
     Ok(())
 }
@@ -3,11 +3,17 @@ extern crate log;
 extern crate structopt;
 
 use anyhow::Result;
+use changelog::imports::from_git_repo::GitImportOptions;
 
-use crate::cli::Command;
+use crate::cli::{Command, ConfigCommand};
 
+mod cmd_config;
+mod cmd_export;
+mod cmd_ignore;
 mod cmd_import_git;
 mod cmd_info;
+mod cmd_lint;
+mod cmd_merge;
 mod cmd_new;
 mod cmd_sync;
 
@@ -24,21 +30,67 @@ fn run_cli() -> Result<()> {
     // process options
     // process commands
     match args.cmd {
-        Command::NewChangelog {} => cmd_new::cmd_new(&args.changelog_file).map_err(|e| e.into()),
-        Command::InitFromGit { stop_version } => {
-            cmd_import_git::cmd_import_git(&args.changelog_file, &args.dir, stop_version)
+        Command::NewChangelog { style } => {
+            cmd_new::cmd_new(&args.changelog_file, &args.dir, style).map_err(|e| e.into())
+        }
+        Command::InitFromGit {
+            stop_version,
+            from,
+            to,
+            traversal,
+        } => {
+            let options = GitImportOptions {
+                stop_version,
+                from,
+                to,
+                traversal,
+            };
+            cmd_import_git::cmd_import_git(&args.changelog_file, &args.dir, &options)
                 .map_err(|e| e.into())
         }
-        Command::Info {} => cmd_info::cmd_info(&args.changelog_file).map_err(|e| e.into()),
+        Command::Info {} => {
+            cmd_info::cmd_info(&args.changelog_file, &args.dir).map_err(|e| e.into())
+        }
         Command::SyncFromGit { .. } => {
             cmd_sync::cmd_sync(&args.changelog_file, &args.dir).map_err(|e| e.into())
         }
+        Command::Lint { all } => {
+            cmd_lint::cmd_lint(&args.changelog_file, &args.dir, all).map_err(|e| e.into())
+        }
+        Command::Ignore { references } => {
+            cmd_ignore::cmd_ignore(&args.changelog_file, &args.dir, &references)
+                .map_err(|e| e.into())
+        }
+        Command::Merge { format, source } => {
+            cmd_merge::cmd_merge(&args.changelog_file, &args.dir, format, &source)
+                .map_err(|e| e.into())
+        }
+        Command::Config {
+            cmd: ConfigCommand::Show,
+        } => cmd_config::cmd_config_show(&args.changelog_file, &args.dir).map_err(|e| e.into()),
+        Command::Export {
+            format,
+            fragment,
+            output,
+            spec,
+        } => cmd_export::cmd_export(
+            &args.changelog_file,
+            &args.dir,
+            format,
+            fragment,
+            output.as_ref(),
+            spec.as_ref(),
+        )
+        .map_err(|e| e.into()),
     }
 }
 
 mod cli {
     use std::path::PathBuf;
 
+    use changelog::bootstrap::LayoutStyle;
+    use changelog::exports::ExportFormat;
+    use changelog::imports::from_git_repo::Traversal;
     use structopt::StructOpt;
 
     /// Changelog toolkit
@@ -66,18 +118,78 @@ mod cli {
     #[derive(StructOpt, Debug)]
     pub enum Command {
         #[structopt(name = "new")]
-        NewChangelog {},
+        /// Create a changelog, configured per the git repo
+        NewChangelog {
+            /// layout: default, grouped or keep-a-changelog
+            #[structopt(long = "style", default_value = "default")]
+            style: LayoutStyle,
+        },
         #[structopt(name = "init")]
         /// Read from git repo
         InitFromGit {
             /// stop parsing git on some version (must match exactly!)
             #[structopt(long = "stop-version")]
             stop_version: Option<String>,
+            /// start after this revision (tag, branch, commit)
+            #[structopt(long = "from")]
+            from: Option<String>,
+            /// end with this revision instead of HEAD
+            #[structopt(long = "to")]
+            to: Option<String>,
+            /// history traversal: first-parent, topological or merges
+            #[structopt(long = "traversal")]
+            traversal: Option<Traversal>,
         },
         /// Show some info about current changelog
         Info {},
         #[structopt(name = "sync")]
         SyncFromGit {},
+        /// Check items per the `[lint]` config
+        Lint {
+            /// check released items too, not just Unreleased
+            #[structopt(long = "all")]
+            all: bool,
+        },
+        /// Never let sync add items with given refs again
+        Ignore {
+            /// refs like `PR#123` or `#456`, or commit ids
+            #[structopt(required = true)]
+            references: Vec<String>,
+        },
+        /// Sync items and releases from another changelog file
+        Merge {
+            /// format of the source: markdown or debian
+            #[structopt(long = "format", default_value = "debian")]
+            format: ExportFormat,
+            /// changelog to merge from, like `debian/changelog`
+            source: PathBuf,
+        },
+        /// Render the changelog in another format
+        Export {
+            /// output format: markdown, html, atom, rss, debian, rpm, asciidoc or rst
+            #[structopt(long = "format", default_value = "html")]
+            format: ExportFormat,
+            /// html: only the changelog element, to embed in another page
+            #[structopt(long = "fragment")]
+            fragment: bool,
+            /// output file; stdout if not set
+            #[structopt(short = "o", long = "output")]
+            output: Option<PathBuf>,
+            /// rpm: replace the `%changelog` section of this spec file, in place
+            #[structopt(long = "spec", conflicts_with = "output")]
+            spec: Option<PathBuf>,
+        },
+        /// Inspect config from `Cargo.toml`, `.changelog.toml`, `chg.toml` and the changelog
+        Config {
+            #[structopt(subcommand)]
+            cmd: ConfigCommand,
+        },
+    }
+
+    #[derive(StructOpt, Debug)]
+    pub enum ConfigCommand {
+        /// Print the effective config, with the source of each value
+        Show,
     }
 
     impl Cli {
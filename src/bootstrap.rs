@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use git2::Repository;
+use regex::Regex;
+
+use crate::builder::ChangeLogBuilder;
+use crate::config::DEFAULT_TAG_VERSION_PATTERN;
+use crate::error::ChgError;
+use crate::hosting::HostedRepo;
+use crate::{ChangeLog, ChangeLogConfig};
+
+const PROLOG: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.";
+
+const KEEP_A_CHANGELOG_PROLOG: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).";
+
+/// Markdown layout of a new changelog.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayoutStyle {
+    /// built-in layout, with refs and authors
+    Default,
+    /// built-in items, grouped by change type
+    Grouped,
+    /// like <https://keepachangelog.com>, with comparison links if the hosting is known
+    KeepAChangelog,
+}
+
+impl FromStr for LayoutStyle {
+    type Err = ChgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "default" => Ok(LayoutStyle::Default),
+            "grouped" => Ok(LayoutStyle::Grouped),
+            "keep-a-changelog" | "keepachangelog" => Ok(LayoutStyle::KeepAChangelog),
+            _ => Err(ChgError::UnknownLayoutStyle(s.to_string())),
+        }
+    }
+}
+
+impl ChangeLog {
+    /// New changelog with a prolog and embedded config, pre-filled from the git repository in `dir`, if any.
+    pub fn bootstrap(
+        dir: &Path,
+        mut config: ChangeLogConfig,
+        style: LayoutStyle,
+    ) -> Result<ChangeLog, ChgError> {
+        let hosted = match Repository::discover(dir) {
+            Err(_) => None,
+            Ok(repo) => {
+                if config.git.tag_version_pattern.is_empty() {
+                    if let Some(pattern) = detect_tag_pattern(&repo)? {
+                        if pattern != DEFAULT_TAG_VERSION_PATTERN {
+                            config.git.tag_version_pattern = pattern;
+                        }
+                    }
+                }
+                HostedRepo::from_repository(&repo, &config.keys.hosting)?
+            }
+        };
+        if let Some(hosted) = &hosted {
+            for (name, link) in hosted.links() {
+                config.embed_link(name, link);
+            }
+        }
+        let templates = &mut config.templates;
+        let prolog = match style {
+            LayoutStyle::Default => PROLOG,
+            LayoutStyle::Grouped => {
+                templates.type_heading = "### {type}".to_string();
+                PROLOG
+            }
+            LayoutStyle::KeepAChangelog => {
+                templates.heading =
+                    "## [{version}] - {date}{#yanked} [{yanked}]{/yanked}".to_string();
+                templates.item =
+                    "- {text}{#refs} ({ref_links}){/refs}{#commit} {commit}{/commit}".to_string();
+                templates.type_heading = "### {type}".to_string();
+                if let Some(hosted) = &hosted {
+                    let compare = hosted.compare_path(
+                        &config.version_to_tag("{previous}"),
+                        &config.version_to_tag("{version}"),
+                    );
+                    config.templates.footer = format!(
+                        "[{{version}}]: {}/{{#previous}}{}{{/previous}}",
+                        hosted.web_url, compare
+                    );
+                }
+                KEEP_A_CHANGELOG_PROLOG
+            }
+        };
+        let embedded = config.to_string_embedded()?;
+        let mut builder = ChangeLogBuilder::new(config);
+        builder.section(None);
+        let mut changelog = builder.build();
+        changelog.prolog = format!("{}\n\n{}", prolog, embedded);
+        Ok(changelog)
+    }
+}
+
+/// Most common naming of version tags, like `v{version}` or `release-{version}`.
+fn detect_tag_pattern(repo: &Repository) -> Result<Option<String>, ChgError> {
+    let regex = Regex::new(r"^(?P<prefix>.*?)\d+(?:\.\d+)+").unwrap();
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for name in repo.tag_names(None)?.iter().flatten() {
+        if let Some(captures) = regex.captures(name) {
+            let suffix = &name[captures.get(0).unwrap().end()..];
+            // pre-release or build suffixes are part of the version
+            if suffix.is_empty() || suffix.starts_with(['-', '+']) {
+                *counts.entry(captures["prefix"].to_string()).or_default() += 1;
+            }
+        }
+    }
+    let prefix = counts
+        .into_iter()
+        .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)));
+    Ok(prefix.map(|(prefix, _)| format!("{}{{version}}", prefix)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bootstrap::LayoutStyle;
+    use crate::discovery::ConfigLayers;
+    use crate::test_util::TempDir;
+    use crate::ChangeLog;
+
+    #[test]
+    fn bootstrap_roundtrip() {
+        let temp = TempDir::new("bootstrap");
+        let dir = temp.0.clone();
+        let repo = git2::Repository::init(&dir).unwrap();
+        repo.remote("origin", "git@github.com:o/r.git").unwrap();
+        let sig = git2::Signature::now("Petr Kozelka", "pk@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        let oid = repo
+            .commit(None, &sig, &sig, "Initial commit", &tree, &[])
+            .unwrap();
+        let commit = repo.find_object(oid, None).unwrap();
+        repo.tag_lightweight("release-1.0.0", &commit, false)
+            .unwrap();
+        repo.tag_lightweight("release-1.1.0-rc.1", &commit, false)
+            .unwrap();
+        repo.tag_lightweight("v0.9", &commit, false).unwrap();
+
+        // like `chg new`, with links derived from the remote by config discovery
+        let config = ConfigLayers::discover(&dir).unwrap().config().unwrap();
+        let changelog = ChangeLog::bootstrap(&dir, config, LayoutStyle::KeepAChangelog).unwrap();
+        let config = &changelog.config;
+        assert_eq!(config.git.tag_version_pattern, "release-{version}");
+        assert_eq!(
+            config.keys.issue_link,
+            "https://github.com/o/r/issues/{number}"
+        );
+        assert_eq!(
+            config.templates.footer,
+            "[{version}]: https://github.com/o/r/{#previous}compare/release-{previous}...release-{version}{/previous}"
+        );
+
+        let mut out = Vec::new();
+        changelog.print_markdown(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("# Changelog\n"), "{}", text);
+        assert!(text.ends_with("-->\n\n## Unreleased\n"), "{}", text);
+        assert!(text.contains("commit_link = "), "{}", text);
+        let parsed = ChangeLog::import_markdown(&text).unwrap();
+        assert_eq!(parsed.config.templates.type_heading, "### {type}");
+        assert_eq!(
+            parsed.config.keys.pr_link,
+            "https://github.com/o/r/pull/{number}"
+        );
+    }
+}
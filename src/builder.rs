@@ -27,10 +27,14 @@ impl ChangeLogBuilder {
         }
     }
 
+    pub fn config(&self) -> &ChangeLogConfig {
+        &self.changelog.config
+    }
+
     pub fn section(&mut self, release: Option<ReleaseHeader>) {
         self.current_section_close();
         self.current_release = release;
-        self.current_section = Some(ChangeSet { items: vec![] });
+        self.current_section = Some(ChangeSet::default());
     }
 
     fn current_section_close(&mut self) {
@@ -44,7 +48,10 @@ impl ChangeLogBuilder {
         }
     }
 
-    pub fn item(&mut self, item: ChangeItem) -> Result<()> {
+    /// Adds item to current section; its refs linking per `[keys]` become what they link to.
+    pub fn item(&mut self, mut item: ChangeItem) -> Result<()> {
+        let config = &self.changelog.config;
+        item.refs = item.refs.into_iter().map(|r| config.canonical(r)).collect();
         let section = match &mut self.current_section {
             None => {
                 return Err(Error::new(
@@ -58,6 +65,34 @@ impl ChangeLogBuilder {
         Ok(())
     }
 
+    /// Appends a line to details of the last item in current section; false if there is no such item.
+    pub fn item_details(&mut self, line: &str) -> bool {
+        let item = match &mut self.current_section {
+            Some(section) => section.items.last_mut(),
+            None => None,
+        };
+        match item {
+            None => false,
+            Some(item) => {
+                if !item.details.is_empty() {
+                    item.details.push('\n');
+                }
+                item.details.push_str(line);
+                true
+            }
+        }
+    }
+
+    /// Appends text to the notes of current section.
+    pub fn release_notes(&mut self, text: &str) {
+        if let Some(section) = &mut self.current_section {
+            if !section.notes.is_empty() {
+                section.notes.push('\n');
+            }
+            section.notes.push_str(text);
+        }
+    }
+
     pub fn note(&mut self, line: &str) -> Result<()> {
         self.current_section_close();
         if self.changelog.releases.is_empty() && self.changelog.unreleased.is_none() {
@@ -105,6 +140,9 @@ mod tests {
                 component: "".to_string(),
                 text: "".to_string(),
                 authors: vec![],
+                commit: None,
+                details: "".to_string(),
+                type_confidence: None,
             })
             .unwrap();
         // epilog
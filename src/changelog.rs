@@ -1,103 +1,401 @@
-use std::collections::HashSet;
-
 use crate::{ChangeLog, ChgError};
-use crate::api::{ChangeSet, ReleaseHeader};
+use crate::layout::MarkdownLayout;
+use crate::api::{same_commit, ChangeItem, ChangeSet, Reference, ReleaseHeader, SyncConflict, SyncReport};
+
+const DEFAULT_SIMILARITY: f64 = 0.85;
 
 impl ChangeLog {
     /// Finds missing items in the changelog.
-///
-/// The general assumptions are:
-/// - `old` is manually updated and must be fully respected
-/// - `new` is auto-generated content, and is rather a recommendation
-/// - presence of items is based on presence of URLs (issues, PRs, other)
-/// - `new` contains most records of `old` (but some may have been manually added or removed)
-///
-/// Situations:
-/// * a) both changelogs have the same LATEST_RELEASE version
-/// * b) `new` comes with one or more releases - then the `Unreleased` changeset is matched against first(oldest) additional release; it's good when there is an item prooving the match
-/// * c) release histories are disjunct => error, cannot update
-/// * d) LATEST_RELEASE of `old` is not present in new => error, cannot update
-    pub fn sync_from(&mut self, new: &ChangeLog) -> Result<(), ChgError> {
-        // bring missing (NEW) released items into OLD unreleased section, and move it to OLD releases
-        // bring following comple
-        // te releases omitted in OLD side (if any)
-        // bring missing NEW unreleased items into OLD unreleased section
-
-        let (old_rvs, _old_changeset) = self
-            .releases
-            .get(0)
-            .expect("TODO: Old changeset has no release yet"); // TODO
-        let (new_rvs, _new_changeset) = new
-            .releases
-            .get(0)
-            .expect("TODO: New changeset has no release yet"); // TODO
-
-        if old_rvs.version == new_rvs.version {
-            // only sync new unreleased into old unreleased
-            let mut old_unreleased = match &self.unreleased {
-                None => ChangeSet { items: vec![] },
-                Some(_) => self.unreleased.take().unwrap(),
-            };
-            changeset_sync(&mut old_unreleased, new.unreleased.as_ref().unwrap());
-            self.unreleased = Some(old_unreleased);
-        } else {
-            // find all new changesets
-            let mut newcs: Vec<&(ReleaseHeader, ChangeSet)> = new
-                .releases
-                .iter()
-                .take_while(|(rvs, _)| rvs.version != old_rvs.version)
-                .collect();
-            newcs.reverse();
+    ///
+    /// The general assumptions are:
+    /// - `old` is manually updated and must be fully respected
+    /// - `new` is auto-generated content, and is rather a recommendation
+    /// - presence of items is based on source commit id, then on presence of URLs (issues, PRs, other),
+    ///   and finally on similar text (ambiguous matches are reported, not guessed)
+    /// - `new` contains most records of `old` (but some may have been manually added or removed)
+    ///
+    /// Situations:
+    /// * a) both changelogs have the same LATEST_RELEASE version
+    /// * b) `new` comes with one or more releases - then the `Unreleased` changeset is matched against first(oldest) additional release; it's good when there is an item prooving the match
+    /// * c) release histories are disjunct => error, cannot update
+    /// * d) LATEST_RELEASE of `old` is not present in new => error, cannot update
+    ///
+    /// When `old` has no release yet, all releases of `new` are considered additional.
+    ///
+    /// Items deleted by hand since last sync are put on the ignore list, so that they are not added again.
+    pub fn sync_from(&mut self, new: &ChangeLog) -> Result<SyncReport, ChgError> {
+        let mut report = SyncReport::default();
+        self.ignore_vanished(&mut report);
+
+        // additional releases, from the newest one
+        let additional: &[(ReleaseHeader, ChangeSet)] = match self.releases.get(0) {
+            None => &new.releases,
+            Some((old_rh, _)) => {
+                match new.releases.iter().position(|(rh, _)| rh.version == old_rh.version) {
+                    Some(pos) => &new.releases[0..pos],
+                    None => {
+                        let common = new.releases.iter().any(|(rh, _)| self.find_release(&rh.version).is_some());
+                        return if common || new.releases.is_empty() {
+                            Err(ChgError::MissingLatestRelease(old_rh.version.clone()))
+                        } else {
+                            Err(ChgError::DisjointHistories)
+                        };
+                    }
+                }
+            }
+        };
+
+        // releases known to both sides
+        for (new_rh, _) in &new.releases[additional.len()..] {
+            if let Some((old_rh, _)) = self.find_release(&new_rh.version) {
+                if old_rh.timestamp != new_rh.timestamp {
+                    report.conflicts.push(SyncConflict::DateMismatch {
+                        version: new_rh.version.clone(),
+                        old: old_rh.timestamp,
+                        new: new_rh.timestamp,
+                    });
+                }
+            }
+        }
 
+        let guard = SyncGuard::new(self);
+
+        let mut additional = additional.iter().rev();
+        if let Some((new_rh, new_changeset)) = additional.next() {
             // 1. old unreleased receives oldest new release
-            let mut old_unreleased = match &self.unreleased {
-                None => ChangeSet { items: vec![] },
-                Some(_) => self.unreleased.take().unwrap(),
-            };
-            let (new_release_header, new_release_changeset) = newcs.remove(0);
-            trace!("1. syncing unreleased news from {}", new_release_header.version);
-            changeset_sync(&mut old_unreleased, new_release_changeset);
+            let mut old_unreleased = self.unreleased.take().unwrap_or_default();
+            for item in &old_unreleased.items {
+                report.moved.push((new_rh.version.clone(), item.clone()));
+            }
+            trace!("1. syncing unreleased news from {}", new_rh.version);
+            changeset_sync(&mut old_unreleased, new_changeset, Some(&new_rh.version), &guard, &mut report);
 
             // 2. old unreleased becomes release
-            trace!("2. switching old unreleased to release: {}", new_release_header.version);
-            self.releases.insert(0, (new_release_header.clone(), old_unreleased));
-            // 3. other new releases are copied to old releases, keeping order
-            for r in newcs {
-                trace!("3. copying entire section {}", r.0.version);
-                self.releases.insert(0, r.clone());
-            }
-            // 4. new unreleased is copied to old unreleased
-            if let Some(unreleased) = &new.unreleased {
-                trace!("4. adding new unreleased section ({} items)", unreleased.items.len());
-                for item in &unreleased.items {
-                    trace!("   * {:?}", item);
+            trace!("2. switching old unreleased to release: {}", new_rh.version);
+            self.releases.insert(0, (new_rh.clone(), old_unreleased));
+            report.releases_created.push(new_rh.version.clone());
+        }
+        // 3. other new releases are copied to old releases, keeping order
+        for (new_rh, new_changeset) in additional {
+            trace!("3. copying entire section {}", new_rh.version);
+            let changeset = changeset_copy(new_changeset, Some(&new_rh.version), &guard, &mut report);
+            self.releases.insert(0, (new_rh.clone(), changeset));
+            report.releases_created.push(new_rh.version.clone());
+        }
+        // 4. new unreleased items are added to old unreleased
+        if let Some(new_unreleased) = &new.unreleased {
+            match &mut self.unreleased {
+                None => {
+                    trace!("4. adding new unreleased section ({} items)", new_unreleased.items.len());
+                    self.unreleased = Some(changeset_copy(new_unreleased, None, &guard, &mut report));
+                }
+                Some(old_unreleased) => {
+                    trace!("4. syncing unreleased section ({} items)", new_unreleased.items.len());
+                    changeset_sync(old_unreleased, new_unreleased, None, &guard, &mut report);
                 }
-                self.unreleased = new.unreleased.clone();
             }
         }
+        self.remember_known(&mut report);
+        Ok(report)
+    }
+
+    /// Removes items with given ref or commit id from the `Unreleased` section, and puts it on the ignore list.
+    pub fn ignore(&mut self, reference: &str) -> Vec<ChangeItem> {
+        let key = self.config.parse_reference(reference);
+        let mut removed = Vec::new();
+        if let Some(unreleased) = &mut self.unreleased {
+            let (matching, rest) = unreleased
+                .items
+                .drain(..)
+                .partition(|item| item.has_key(&key));
+            unreleased.items = rest;
+            removed = matching;
+        }
+        let config = &self.config;
+        let ignored = config.sync.ignore.iter().any(|r| config.parse_reference(r) == key);
+        let known: Vec<String> = config
+            .sync
+            .known
+            .iter()
+            .filter(|r| config.parse_reference(r) != key)
+            .cloned()
+            .collect();
+        let sync = &mut self.config.sync;
+        if !ignored {
+            sync.ignore.push(key.to_string());
+        }
+        sync.known = known;
+        removed
+    }
+
+    /// Writes current config into the embedded config block, or appends a new block to the epilog.
+    pub fn store_config(&mut self) -> Result<(), ChgError> {
+        if let Some(prolog) = self.config.replace_embedded(&self.prolog)? {
+            self.prolog = prolog;
+        } else if let Some(epilog) = self.config.replace_embedded(&self.epilog)? {
+            self.epilog = epilog;
+        } else {
+            self.epilog.push('\n');
+            self.epilog.push_str(&self.config.to_string_embedded()?);
+        }
         Ok(())
     }
+
+    /// Moves refs and commit ids known from last sync, but no longer present anywhere in the changelog, to the ignore list.
+    /// Commit ids only count when the layout writes them; otherwise they always vanish.
+    fn ignore_vanished(&mut self, report: &mut SyncReport) {
+        let commits_kept = self.renders_commit();
+        let items: Vec<&ChangeItem> = self
+            .unreleased
+            .iter()
+            .chain(self.releases.iter().map(|(_, changeset)| changeset))
+            .flat_map(|changeset| changeset.items.iter())
+            .collect();
+        let vanished: Vec<String> = self
+            .config
+            .sync
+            .known
+            .iter()
+            .filter(|key| {
+                let key = self.config.parse_reference(key);
+                let is_commit = matches!(key, Reference::Commit(_));
+                (commits_kept || !is_commit) && !items.iter().any(|item| item.has_key(&key))
+            })
+            .cloned()
+            .collect();
+        for reference in vanished {
+            debug!("{} was deleted since last sync, ignoring it from now on", reference);
+            self.config.sync.ignore.push(reference.clone());
+            report.newly_ignored.push(reference);
+            report.state_changed = true;
+        }
+    }
+
+    /// Records refs and commit ids of the `Unreleased` section, to detect items deleted before the next sync.
+    fn remember_known(&mut self, report: &mut SyncReport) {
+        let commits_kept = self.renders_commit();
+        let mut known: Vec<String> = Vec::new();
+        if let Some(unreleased) = &self.unreleased {
+            for item in &unreleased.items {
+                for key in item.keys() {
+                    if !commits_kept && matches!(key, Reference::Commit(_)) {
+                        continue;
+                    }
+                    let key = key.to_string();
+                    if !known.contains(&key) {
+                        known.push(key);
+                    }
+                }
+            }
+        }
+        if known != self.config.sync.known {
+            self.config.sync.known = known;
+            report.state_changed = true;
+        }
+    }
+
+    fn renders_commit(&self) -> bool {
+        MarkdownLayout::from_config(&self.config).is_ok_and(|layout| layout.renders_commit())
+    }
+
+    fn find_release(&self, version: &str) -> Option<&(ReleaseHeader, ChangeSet)> {
+        self.releases.iter().find(|(rh, _)| rh.version == version)
+    }
 }
-fn changeset_sync(this: &mut ChangeSet, from: &ChangeSet) {
-    // gather all urls on `this` side
-    let mut this_urls = HashSet::new();
-    {
-        for item in &this.items {
-            for href in &item.refs {
-                this_urls.insert(href.clone());
+
+/// Decides which of the new items must not be added.
+struct SyncGuard {
+    /// refs already released, which must not appear again, with their version
+    released_refs: Vec<(Reference, String)>,
+    ignored: Vec<Reference>,
+    /// text similarity threshold for matching items
+    similarity: f64,
+}
+
+impl SyncGuard {
+    fn new(changelog: &ChangeLog) -> Self {
+        let mut released_refs: Vec<(Reference, String)> = Vec::new();
+        for (rh, changeset) in &changelog.releases {
+            for item in &changeset.items {
+                for href in &item.refs {
+                    if !released_refs.iter().any(|(r, _)| r == href) {
+                        released_refs.push((href.clone(), rh.version.clone()));
+                    }
+                }
             }
         }
+        let config = &changelog.config;
+        let ignored = config.sync.ignore.iter().map(|r| config.parse_reference(r)).collect();
+        let similarity = changelog.config.sync.similarity.unwrap_or(DEFAULT_SIMILARITY);
+        Self { released_refs, ignored, similarity }
     }
-    // go through `from` items; for each with missing url on `this`, add it
-    for item in from.items.iter().rev() {
+
+    /// Returns false, and reports why, if the item must not be added.
+    fn allows(&self, item: &ChangeItem, report: &mut SyncReport) -> bool {
+        if let Some(key) = self.ignored.iter().find(|key| item.has_key(key)) {
+            trace!("not adding '{:?}' because {} is ignored", item, key);
+            report.ignored.push(item.clone());
+            return false;
+        }
         for href in &item.refs {
-            if !this_urls.contains(href) {
+            if let Some((_, released)) = self.released_refs.iter().find(|(r, _)| r == href) {
+                trace!("not adding '{:?}' because {} is released in {}", item, href, released);
+                report.conflicts.push(SyncConflict::AlreadyReleased {
+                    item: item.clone(),
+                    reference: href.clone(),
+                    version: released.clone(),
+                });
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Copies all items of `from`, except those that must not be added.
+fn changeset_copy(
+    from: &ChangeSet,
+    version: Option<&str>,
+    guard: &SyncGuard,
+    report: &mut SyncReport,
+) -> ChangeSet {
+    let mut this = ChangeSet {
+        items: vec![],
+        notes: from.notes.clone(),
+    };
+    for item in &from.items {
+        if !guard.allows(item, report) {
+            continue;
+        }
+        trace!("   * {:?}", item);
+        this.items.push(item.clone());
+        report.added.push((version.map(str::to_string), item.clone()));
+    }
+    this
+}
+
+/// Adds items of `from` that are missing in `this`, keeping their order.
+/// Items that must not be added are reported instead.
+fn changeset_sync(
+    this: &mut ChangeSet,
+    from: &ChangeSet,
+    version: Option<&str>,
+    guard: &SyncGuard,
+    report: &mut SyncReport,
+) {
+    if this.notes.is_empty() {
+        this.notes = from.notes.clone();
+    }
+    let mut added = Vec::new();
+    for item in from.items.iter().rev() {
+        match find_match(&this.items, item, guard.similarity) {
+            ItemMatch::Found(existing) => {
+                trace!("'{}' is present as '{}'", item.text, existing.text);
+            }
+            ItemMatch::Ambiguous(candidates) => {
+                trace!("'{}' is similar to {} items", item.text, candidates.len());
+                report.conflicts.push(SyncConflict::AmbiguousMatch {
+                    item: item.clone(),
+                    candidates,
+                });
+            }
+            ItemMatch::Missing => {
+                if !guard.allows(item, report) {
+                    continue;
+                }
+                trace!("adding '{:?}'", item);
                 this.items.insert(0, item.clone());
-                //TODO also save a command!
-                trace!("adding '{:?}' because of {}", item, href);
-                for r in &item.refs { this_urls.insert(r.clone()); }
-                break;
+                added.push((version.map(str::to_string), item.clone()));
             }
         }
     }
+    added.reverse();
+    report.added.append(&mut added);
+}
+
+enum ItemMatch<'a> {
+    Found(&'a ChangeItem),
+    Ambiguous(Vec<ChangeItem>),
+    Missing,
+}
+
+/// Finds the item among `items`, by commit id, then by refs, then by similar text.
+fn find_match<'a>(items: &'a [ChangeItem], item: &ChangeItem, similarity: f64) -> ItemMatch<'a> {
+    if let Some(commit) = &item.commit {
+        let found = items.iter().find(|i| i.commit.as_ref().is_some_and(|c| same_commit(c, commit)));
+        if let Some(found) = found {
+            return ItemMatch::Found(found);
+        }
+    }
+    if let Some(found) = items.iter().find(|i| i.refs.iter().any(|r| item.refs.contains(r))) {
+        return ItemMatch::Found(found);
+    }
+    // candidates with different identity are out
+    let text = normalize_text(&item.text);
+    let candidates: Vec<&ChangeItem> = items
+        .iter()
+        .filter(|i| match (&i.commit, &item.commit) {
+            (Some(a), Some(b)) => same_commit(a, b),
+            _ => true,
+        })
+        .filter(|i| i.refs.is_empty() || item.refs.is_empty())
+        .filter(|i| text_similarity(&normalize_text(&i.text), &text) >= similarity)
+        .collect();
+    if let Some(same) = candidates.iter().find(|i| normalize_text(&i.text) == text) {
+        return ItemMatch::Found(same);
+    }
+    match candidates.len() {
+        0 => ItemMatch::Missing,
+        1 => ItemMatch::Found(candidates[0]),
+        _ => ItemMatch::Ambiguous(candidates.into_iter().cloned().collect()),
+    }
+}
+
+/// Lowercase words, separated by single space.
+fn normalize_text(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Dice coefficient of character bigrams; 1.0 for equal texts.
+fn text_similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+    let bigrams = |s: &str| -> Vec<(char, char)> {
+        let chars: Vec<char> = s.chars().collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect()
+    };
+    let a = bigrams(a);
+    let mut b = bigrams(b);
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let total = a.len() + b.len();
+    let mut common = 0;
+    for bigram in &a {
+        if let Some(pos) = b.iter().position(|x| x == bigram) {
+            b.swap_remove(pos);
+            common += 1;
+        }
+    }
+    2.0 * common as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::changelog::{normalize_text, text_similarity};
+
+    #[test]
+    fn similarity() {
+        let a = normalize_text("Fix NPE when parsing empty mojo.");
+        let b = normalize_text("fix npe when parsing an empty MOJO");
+        assert_eq!(a, "fix npe when parsing empty mojo");
+        assert!(text_similarity(&a, &b) > 0.85, "{}", text_similarity(&a, &b));
+        assert!(text_similarity(&a, &normalize_text("Upgrade dependencies")) < 0.3);
+        assert_eq!(text_similarity(&a, &a), 1.0);
+    }
 }
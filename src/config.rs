@@ -1,55 +1,555 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use regex::Regex;
+
+use crate::api::Reference;
 use crate::error::ChgError;
 
 const CHANGELOG_CONFIG_START: &str = "<!-- CHANGELOG-CONFIG";
 const CHANGELOG_CONFIG_END: &str = "-->";
+pub(crate) const DEFAULT_TAG_VERSION_PATTERN: &str = "v{version}";
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct ChangeLogConfig {
-    git: GitConfig,
-    keys: KeysConfig,
+    #[serde(default)]
+    pub(crate) git: GitConfig,
+    #[serde(default)]
+    pub(crate) keys: KeysConfig,
+    #[serde(default)]
+    pub(crate) types: TypesConfig,
+    /// path globs mapped to component names, like `"src/java/**" = "java"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) components: BTreeMap<String, String>,
+    /// commit trailer keys mapped to item fields, like `Jira = "refs"`; see [`TrailerField`](crate::imports::trailers::TrailerField)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) trailers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub(crate) metadata: MetadataConfig,
+    #[serde(default)]
+    pub(crate) sync: SyncConfig,
+    #[serde(default)]
+    pub(crate) feed: FeedConfig,
+    #[serde(default)]
+    pub(crate) debian: DebianConfig,
+    #[serde(default)]
+    pub(crate) rpm: RpmConfig,
+    #[serde(default)]
+    pub(crate) templates: TemplatesConfig,
+    #[serde(default)]
+    pub(crate) lint: LintConfig,
+    /// values from config files outside the changelog; the embedded block only keeps values that differ
+    #[serde(skip)]
+    pub(crate) base: Option<toml::Value>,
+    #[serde(skip)]
+    pub(crate) regexes: RegexCache,
+}
+
+/// Regexes built from config values, by pattern, so that they are compiled once per config.
+#[derive(Debug, Default)]
+pub(crate) struct RegexCache(Mutex<HashMap<String, Option<Regex>>>);
+
+impl RegexCache {
+    /// Compiled pattern, or `None` if it is invalid.
+    pub(crate) fn get(&self, pattern: String) -> Option<Regex> {
+        let mut regexes = self.0.lock().unwrap();
+        regexes
+            .entry(pattern)
+            .or_insert_with_key(|pattern| Regex::new(pattern).ok())
+            .clone()
+    }
+}
+
+impl Clone for RegexCache {
+    fn clone(&self) -> Self {
+        RegexCache(Mutex::new(self.0.lock().unwrap().clone()))
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
-struct GitConfig {
-    tag_version_pattern: String,
+#[serde(default)]
+pub(crate) struct GitConfig {
+    /// release tag name with `{version}` placeholder, like `release-{version}`; defaults to `v{version}`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) tag_version_pattern: String,
+    /// history traversal: `first-parent` (default), `topological` or `merges`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) traversal: String,
+    /// text in annotated tag message that marks the release as yanked, like `[YANKED]`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) yanked_marker: String,
+    /// include commit message body under the item
+    #[serde(skip_serializing_if = "is_false")]
+    pub(crate) details: bool,
+    /// release commit conventions: `default`, `maven`, `cargo`, `npm` or `semantic-release`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) release_profiles: Vec<String>,
+    /// custom release commit subjects, capturing the `(?P<version>...)`; tried before profiles
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) release_patterns: Vec<String>,
+    /// custom post-release commit subjects, like `^Bump to (?P<version>.*)-SNAPSHOT`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) postrelease_patterns: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
-struct KeysConfig {
-    issue_link: String,
+#[serde(default)]
+pub(crate) struct KeysConfig {
+    /// hosting of the git remote: `github`, `gitlab`, `bitbucket`, `gitea` or `azure`; only needed for unknown hosts,
+    /// like self-hosted GitLab on `git.example.com`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) hosting: String,
+    /// link to issue, like `https://github.com/OWNER/REPO/issues/{number}`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) issue_link: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
     issue_key: String,
-    pr_link: String,
+    /// link to pull request, or GitLab merge request, like `https://github.com/OWNER/REPO/pull/{number}`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) pr_link: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
     pr_key: String,
+    /// link to issue in another project, like `https://gitlab.com/{project}/-/issues/{number}` for `group/project#45`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) project_issue_link: String,
+    /// link to merge request in another project, like `https://gitlab.com/{project}/-/merge_requests/{number}`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) project_pr_link: String,
+    /// link to commit, like `https://github.com/OWNER/REPO/commit/{commit}`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub(crate) commit_link: String,
+    /// link to comparison of two tags or commits, like `https://github.com/OWNER/REPO/compare/{from}...{to}`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) compare_link: String,
+    /// other issue trackers by name, like `[keys.trackers.jira]`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) trackers: BTreeMap<String, TrackerConfig>,
+}
+
+/// Issue tracker with its own keys, like Jira.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct TrackerConfig {
+    /// regex of the key, like `PROJ-\d+`
+    pub(crate) key: String,
+    /// link with `{key}` placeholder, like `https://jira.example.com/browse/{key}`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) link: String,
+    /// remove keys from the start and the end of commit subjects, like in `PROJ-12: Fix crash`
+    #[serde(skip_serializing_if = "is_false")]
+    pub(crate) strip: bool,
+}
+
+/// Heuristics for guessing [`ChangeType`](crate::api::ChangeType) of commits that do not declare it.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct TypesConfig {
+    /// leading subject words or bracket tags per change type, like `fixed = ["Fix", "Bugfix"]`;
+    /// replaces the built-in list for that type
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) keywords: BTreeMap<String, Vec<String>>,
+    /// PR labels mapped to change type, like `bug = "fixed"`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) labels: BTreeMap<String, String>,
+}
+
+/// Where to look up PR and issue titles, labels and authors.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct MetadataConfig {
+    /// JSON dump of issues and PRs, like from `gh api repos/OWNER/REPO/issues?state=all --paginate`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) cache_file: String,
+    /// REST API base, like `https://api.github.com/repos/OWNER/REPO`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) api_url: String,
+}
+
+/// State kept between `chg sync` runs.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct SyncConfig {
+    /// refs or commit ids of items that sync must never add again
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) ignore: Vec<String>,
+    /// refs and commit ids of `Unreleased` items after last sync; those missing on next sync were deleted by hand, and get ignored
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) known: Vec<String>,
+    /// minimal text similarity (0.0 - 1.0) for considering an item already present; defaults to 0.85
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) similarity: Option<f64>,
+}
+
+/// Atom and RSS feed of releases.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct FeedConfig {
+    /// feed title; defaults to the changelog title
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) title: String,
+    /// where the changelog is published, like `https://example.com/changelog.html`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) link: String,
+    /// link to a release, like `https://example.com/changelog.html#v{version}`; defaults to `link` with version anchor
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) permalink: String,
+}
+
+/// Constant fields of `debian/changelog` entries.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct DebianConfig {
+    /// source package name
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) package: String,
+    /// like `Jane Doe <jane@example.com>`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) maintainer: String,
+    /// defaults to `unstable`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) distribution: String,
+    /// defaults to `medium`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) urgency: String,
+    /// Debian revision appended to upstream versions; defaults to `1`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) revision: String,
+}
+
+/// Constant fields of RPM spec `%changelog` entries.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct RpmConfig {
+    /// like `Jane Doe <jane@example.com>`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) packager: String,
+    /// RPM release appended to versions; defaults to `1`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) release: String,
+}
+
+/// Rules of `chg lint`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct LintConfig {
+    /// every item needs a key of some tracker from `[keys.trackers]`
+    #[serde(skip_serializing_if = "is_false")]
+    pub(crate) require_tracker_key: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Custom markdown layout; empty values mean the built-in layout.
+///
+/// Placeholders like `{text}` are replaced with values, `{#refs}...{/refs}` is only rendered when `refs` is not empty.
+/// The same templates are used for parsing the changelog back.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct TemplatesConfig {
+    /// release heading, like `## [{version}] - {date}{#yanked} [{yanked}]{/yanked}`; also accepts `{branch}`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) heading: String,
+    /// item line, like `- {text}{#refs} ({ref_links}){/refs}{#authors} — {authors}{/authors}`;
+    /// also accepts `{component}`, `{commit}` and `{type}`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) item: String,
+    /// heading of items grouped by change type, like `### {type}`; items are not grouped if empty
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) type_heading: String,
+    /// line per release after all releases, like `[{version}]: https://github.com/OWNER/REPO/compare/v{previous}...v{version}`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) footer: String,
 }
 
 impl ChangeLogConfig {
     pub fn parse_embedded(text: &str) -> Result<ChangeLogConfig, ChgError> {
-        let config = match text.find(CHANGELOG_CONFIG_START) {
+        let config = match Self::embedded_text(text)? {
             None => ChangeLogConfig::default(),
+            Some(text) => {
+                toml::from_str(text).map_err(|e| ChgError::ConfigReadError(e.to_string()))?
+            }
+        };
+        Ok(config)
+    }
+
+    /// TOML text of the embedded config block, if present.
+    pub(crate) fn embedded_text(text: &str) -> Result<Option<&str>, ChgError> {
+        match text.find(CHANGELOG_CONFIG_START) {
+            None => Ok(None),
             Some(start) => {
-                let text = text[start..].trim_start();
+                let text = &text[start + CHANGELOG_CONFIG_START.len()..];
                 match text.find(CHANGELOG_CONFIG_END) {
-                    None => {
-                        return Err(ChgError::ConfigReadError(
-                            "missing end delimiter for embedded config".to_string(),
-                        ));
-                    }
-                    Some(end) => {
-                        let text = text[0..end].trim_end();
-                        toml::from_str(text)
-                            .map_err(|e| ChgError::ConfigReadError(e.to_string()))?
-                    }
+                    None => Err(ChgError::ConfigReadError(
+                        "missing end delimiter for embedded config".to_string(),
+                    )),
+                    Some(end) => Ok(Some(text[0..end].trim_end())),
                 }
             }
-        };
-        Ok(config)
+        }
     }
 
     pub fn to_string_embedded(&self) -> Result<String, ChgError> {
-        let config_text = toml::to_string(&self).map_err(|_| ChgError::ConfigWriteError)?;
+        let mut value = toml::Value::try_from(self).map_err(|_| ChgError::ConfigWriteError)?;
+        let base = self
+            .base
+            .clone()
+            .unwrap_or_else(|| toml::Value::Table(Default::default()));
+        prune_inherited(&mut value, &base);
+        let config_text = toml::to_string(&value).map_err(|_| ChgError::ConfigWriteError)?;
         Ok(format!(
             "{}\n{}{}",
             CHANGELOG_CONFIG_START, config_text, CHANGELOG_CONFIG_END
         ))
     }
+
+    fn tag_pattern(&self) -> &str {
+        if self.git.tag_version_pattern.is_empty() {
+            DEFAULT_TAG_VERSION_PATTERN
+        } else {
+            &self.git.tag_version_pattern
+        }
+    }
+
+    /// Version of a release tag, like `1.2.3` from `v1.2.3`; `None` if the tag does not match `git.tag_version_pattern`.
+    pub fn tag_to_version(&self, tag_name: &str) -> Option<String> {
+        let pattern = self.tag_pattern();
+        let (prefix, suffix) = match pattern.find("{version}") {
+            None => (pattern, ""),
+            Some(start) => (&pattern[..start], &pattern[start + "{version}".len()..]),
+        };
+        let version = tag_name.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        if version.is_empty() {
+            None
+        } else {
+            Some(version.to_string())
+        }
+    }
+
+    /// Release tag name of a version, per `git.tag_version_pattern`.
+    pub fn version_to_tag(&self, version: &str) -> String {
+        self.tag_pattern().replace("{version}", version)
+    }
+
+    /// Link to the commit, if configured.
+    pub fn commit_link(&self, commit: &str) -> Option<String> {
+        if self.keys.commit_link.is_empty() {
+            None
+        } else {
+            Some(self.keys.commit_link.replace("{commit}", commit))
+        }
+    }
+
+    /// Tracker whose key pattern matches the whole reference, like `PROJ-12`; trackers with invalid patterns are skipped.
+    pub(crate) fn tracker_of(&self, reference: &str) -> Option<(&str, &TrackerConfig)> {
+        self.keys.trackers.iter().find_map(|(name, tracker)| {
+            let regex = self.regexes.get(format!("^(?:{})$", tracker.key))?;
+            if !tracker.key.is_empty() && regex.is_match(reference) {
+                Some((name.as_str(), tracker))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Link template from `[keys]` by its config name, like `pr_link`.
+    pub(crate) fn link_mut(&mut self, name: &str) -> Option<&mut String> {
+        let keys = &mut self.keys;
+        match name {
+            "issue_link" => Some(&mut keys.issue_link),
+            "pr_link" => Some(&mut keys.pr_link),
+            "project_issue_link" => Some(&mut keys.project_issue_link),
+            "project_pr_link" => Some(&mut keys.project_pr_link),
+            "commit_link" => Some(&mut keys.commit_link),
+            "compare_link" => Some(&mut keys.compare_link),
+            _ => None,
+        }
+    }
+
+    /// Sets the link template from `[keys]` if empty, and makes sure it gets embedded even if derived from the remote.
+    pub(crate) fn embed_link(&mut self, name: &str, link: String) {
+        let template = match self.link_mut(name) {
+            None => return,
+            Some(template) => template,
+        };
+        if template.is_empty() {
+            *template = link;
+        }
+        let template = toml::Value::String(template.clone());
+        if let Some(keys) = self
+            .base
+            .as_mut()
+            .and_then(|base| base.get_mut("keys"))
+            .and_then(toml::Value::as_table_mut)
+        {
+            if keys.get(name) == Some(&template) {
+                keys.remove(name);
+            }
+        }
+    }
+
+    /// Link to comparison of two revisions, if configured.
+    pub fn compare_link(&self, from: &str, to: &str) -> Option<String> {
+        if self.keys.compare_link.is_empty() {
+            None
+        } else {
+            Some(
+                self.keys
+                    .compare_link
+                    .replace("{from}", from)
+                    .replace("{to}", to),
+            )
+        }
+    }
+
+    /// Link to the reference, if configured; links are themselves.
+    pub fn ref_link(&self, reference: &Reference) -> Option<String> {
+        let (template, project, number) = match reference {
+            Reference::Issue { project, number } if project.is_empty() => {
+                (&self.keys.issue_link, project, number)
+            }
+            Reference::Issue { project, number } => {
+                (&self.keys.project_issue_link, project, number)
+            }
+            Reference::PullRequest {
+                project, number, ..
+            } if project.is_empty() => (&self.keys.pr_link, project, number),
+            Reference::PullRequest {
+                project, number, ..
+            } => (&self.keys.project_pr_link, project, number),
+            Reference::Commit(commit) => return self.commit_link(commit),
+            Reference::ExternalTracker(key) => {
+                let (_, tracker) = self.tracker_of(key)?;
+                if tracker.link.is_empty() {
+                    return None;
+                }
+                return Some(tracker.link.replace("{key}", key));
+            }
+            Reference::Url(url) => return Some(url.clone()),
+        };
+        if template.is_empty() {
+            None
+        } else {
+            Some(
+                template
+                    .replace("{project}", project)
+                    .replace("{number}", &number.to_string()),
+            )
+        }
+    }
+
+    /// Returns the text without the embedded config block.
+    pub(crate) fn strip_embedded(&self, text: &str) -> String {
+        let start = match text.find(CHANGELOG_CONFIG_START) {
+            None => return text.to_string(),
+            Some(start) => start,
+        };
+        match text[start..].find(CHANGELOG_CONFIG_END) {
+            None => text[0..start].to_string(),
+            Some(end) => format!(
+                "{}{}",
+                &text[0..start],
+                &text[start + end + CHANGELOG_CONFIG_END.len()..]
+            ),
+        }
+    }
+
+    /// Replaces the embedded config block in the text with current content.
+    /// Returns `None` if the text contains no such block.
+    pub fn replace_embedded(&self, text: &str) -> Result<Option<String>, ChgError> {
+        let start = match text.find(CHANGELOG_CONFIG_START) {
+            None => return Ok(None),
+            Some(start) => start,
+        };
+        let end = match text[start..].find(CHANGELOG_CONFIG_END) {
+            None => {
+                return Err(ChgError::ConfigReadError(
+                    "missing end delimiter for embedded config".to_string(),
+                ))
+            }
+            Some(end) => start + end + CHANGELOG_CONFIG_END.len(),
+        };
+        Ok(Some(format!(
+            "{}{}{}",
+            &text[0..start],
+            self.to_string_embedded()?,
+            &text[end..]
+        )))
+    }
+}
+
+/// Removes values that are the same in `base`, and tables that end up empty.
+fn prune_inherited(value: &mut toml::Value, base: &toml::Value) {
+    let empty = toml::Value::Table(Default::default());
+    if let (toml::Value::Table(table), toml::Value::Table(base)) = (value, base) {
+        let keys: Vec<String> = table.keys().cloned().collect();
+        for key in keys {
+            let inherited = match (table.get_mut(&key), base.get(&key)) {
+                (Some(value), Some(base_value)) if value == base_value => true,
+                (Some(value), base_value) => {
+                    prune_inherited(value, base_value.unwrap_or(&empty));
+                    matches!(value, toml::Value::Table(t) if t.is_empty())
+                }
+                _ => false,
+            };
+            if inherited {
+                table.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::Reference;
+    use crate::ChangeLogConfig;
+
+    #[test]
+    fn embedded_roundtrip() {
+        let mut config = ChangeLogConfig::default();
+        config.sync.ignore.push("PR#12".to_string());
+        let text = format!(
+            "# Changelog\n{}\n## Unreleased\n",
+            config.to_string_embedded().unwrap()
+        );
+        let parsed = ChangeLogConfig::parse_embedded(&text).unwrap();
+        assert_eq!(parsed.sync.ignore, vec!["PR#12"]);
+
+        config.sync.ignore.push("#13".to_string());
+        let text = config.replace_embedded(&text).unwrap().unwrap();
+        assert!(
+            text.starts_with("# Changelog\n<!-- CHANGELOG-CONFIG"),
+            "{}",
+            text
+        );
+        assert!(text.ends_with("-->\n## Unreleased\n"), "{}", text);
+        let parsed = ChangeLogConfig::parse_embedded(&text).unwrap();
+        assert_eq!(parsed.sync.ignore, vec!["PR#12", "#13"]);
+    }
+
+    #[test]
+    fn ref_links() {
+        let config = ChangeLogConfig::parse_embedded(
+            r#"<!-- CHANGELOG-CONFIG
+[keys]
+issue_link = "https://gitlab.com/g/r/-/issues/{number}"
+pr_link = "https://gitlab.com/g/r/-/merge_requests/{number}"
+project_issue_link = "https://gitlab.com/{project}/-/issues/{number}"
+-->"#,
+        )
+        .unwrap();
+        let ref_link = |text: &str| config.ref_link(&Reference::parse(text));
+        assert_eq!(
+            ref_link("!12").unwrap(),
+            "https://gitlab.com/g/r/-/merge_requests/12"
+        );
+        assert_eq!(ref_link("#3").unwrap(), "https://gitlab.com/g/r/-/issues/3");
+        assert_eq!(
+            ref_link("other/sub/proj#45").unwrap(),
+            "https://gitlab.com/other/sub/proj/-/issues/45"
+        );
+        assert!(ref_link("other/proj!5").is_none());
+        assert!(ref_link("PROJ-1").is_none());
+    }
 }
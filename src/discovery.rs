@@ -0,0 +1,339 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::error::ChgError;
+use crate::hosting::{self, HostedRepo};
+use crate::{ChangeLog, ChangeLogConfig};
+
+/// Config files looked up in the project root, in order of increasing precedence.
+const CONFIG_FILES: &[&str] = &[".changelog.toml", "chg.toml"];
+
+/// Where a config value comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigSource {
+    Default,
+    /// `[package.metadata.changelog]` in `Cargo.toml`
+    CargoMetadata(PathBuf),
+    File(PathBuf),
+    /// block embedded in the changelog
+    Embedded(PathBuf),
+    /// derived from the git remote, with given web URL
+    Remote(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::CargoMetadata(path) => {
+                write!(f, "{} [package.metadata.changelog]", path.display())
+            }
+            ConfigSource::File(path) => write!(f, "{}", path.display()),
+            ConfigSource::Embedded(path) => write!(f, "{} (embedded)", path.display()),
+            ConfigSource::Remote(url) => write!(f, "git remote {}", url),
+        }
+    }
+}
+
+/// Config discovered in the project, as layers of increasing precedence:
+/// `Cargo.toml` metadata, `.changelog.toml`, `chg.toml`, and the block embedded in the changelog.
+/// Link templates left empty by all of them are derived from the git remote.
+pub struct ConfigLayers {
+    layers: Vec<(ConfigSource, toml::Value)>,
+    remote_urls: Vec<String>,
+}
+
+impl ConfigLayers {
+    /// Finds config files in the root of the git repository containing `dir`, or in `dir` itself.
+    pub fn discover(dir: &Path) -> Result<Self, ChgError> {
+        let (root, remote_urls) = match git2::Repository::discover(dir) {
+            Ok(repo) => (
+                repo.workdir().map(Path::to_path_buf),
+                hosting::remote_urls(&repo)?,
+            ),
+            Err(_) => (None, vec![]),
+        };
+        let root = root.unwrap_or_else(|| dir.to_path_buf());
+
+        let mut layers = Vec::new();
+        let cargo_toml = root.join("Cargo.toml");
+        if cargo_toml.is_file() {
+            let manifest = read_toml(&cargo_toml)?;
+            let metadata = manifest
+                .get("package")
+                .and_then(|p| p.get("metadata"))
+                .and_then(|m| m.get("changelog"));
+            if let Some(metadata) = metadata {
+                layers.push((ConfigSource::CargoMetadata(cargo_toml), metadata.clone()));
+            }
+        }
+        for name in CONFIG_FILES {
+            let file = root.join(name);
+            if file.is_file() {
+                let value = read_toml(&file)?;
+                layers.push((ConfigSource::File(file), value));
+            }
+        }
+        Ok(Self {
+            layers,
+            remote_urls,
+        })
+    }
+
+    /// Adds the block embedded in the changelog text, as the top layer.
+    pub fn with_embedded(mut self, changelog_file: &Path, text: &str) -> Result<Self, ChgError> {
+        if let Some(embedded) = ChangeLogConfig::embedded_text(text)? {
+            let value =
+                toml::from_str(embedded).map_err(|e| ChgError::ConfigReadError(e.to_string()))?;
+            self.layers
+                .push((ConfigSource::Embedded(changelog_file.to_path_buf()), value));
+        }
+        Ok(self)
+    }
+
+    /// Effective config; it remembers values from outside the changelog, so that they are not copied into it.
+    pub fn config(&self) -> Result<ChangeLogConfig, ChgError> {
+        let mut merged = toml::Value::Table(Default::default());
+        let mut base = None;
+        for (source, value) in &self.layers {
+            if let ConfigSource::Embedded(_) = source {
+                base = Some(merged.clone());
+            }
+            merge(&mut merged, value);
+        }
+        let mut base = base.unwrap_or_else(|| merged.clone());
+        if let Some((_, derived)) = self.derived(&merged)? {
+            merge(&mut merged, &derived);
+            merge(&mut base, &derived);
+        }
+        let mut config: ChangeLogConfig = merged
+            .try_into()
+            .map_err(|e: toml::de::Error| ChgError::ConfigReadError(e.to_string()))?;
+        config.base = Some(base);
+        Ok(config)
+    }
+
+    /// Link templates derived from the git remote, for those left empty in `merged`.
+    fn derived(
+        &self,
+        merged: &toml::Value,
+    ) -> Result<Option<(ConfigSource, toml::Value)>, ChgError> {
+        let keys = merged.get("keys");
+        let value = |name: &str| {
+            keys.and_then(|k| k.get(name))
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+        };
+        let hosted = match HostedRepo::from_remote_urls(&self.remote_urls, value("hosting"))? {
+            None => return Ok(None),
+            Some(hosted) => hosted,
+        };
+        let mut derived = toml::map::Map::new();
+        for (name, link) in hosted.links() {
+            if value(name).is_empty() {
+                derived.insert(name.to_string(), toml::Value::String(link));
+            }
+        }
+        let mut table = toml::map::Map::new();
+        table.insert("keys".to_string(), toml::Value::Table(derived));
+        Ok(Some((
+            ConfigSource::Remote(hosted.web_url),
+            toml::Value::Table(table),
+        )))
+    }
+
+    /// Effective values as `(key, value, source)`, with dotted keys like `git.traversal`.
+    pub fn provenance(&self) -> Result<Vec<(String, String, ConfigSource)>, ChgError> {
+        let effective =
+            toml::Value::try_from(self.config()?).map_err(|_| ChgError::ConfigWriteError)?;
+        let mut merged = toml::Value::Table(Default::default());
+        for (_, value) in &self.layers {
+            merge(&mut merged, value);
+        }
+        let derived = self.derived(&merged)?;
+        let mut leaves = Vec::new();
+        flatten("", &effective, &mut leaves);
+        Ok(leaves
+            .into_iter()
+            .map(|(key, value)| {
+                let source = derived
+                    .iter()
+                    .chain(self.layers.iter().rev())
+                    .find(|(_, layer)| lookup(layer, &key).is_some())
+                    .map_or(ConfigSource::Default, |(source, _)| source.clone());
+                (key, value.to_string(), source)
+            })
+            .collect())
+    }
+}
+
+impl ChangeLog {
+    /// Reads the changelog file, with config discovered in the project `dir`.
+    pub fn load(changelog_file: &Path, dir: &Path) -> Result<ChangeLog, ChgError> {
+        let text = std::fs::read_to_string(changelog_file)?;
+        let config = ConfigLayers::discover(dir)?
+            .with_embedded(changelog_file, &text)?
+            .config()?;
+        ChangeLog::import_markdown_with(&text, config)
+    }
+}
+
+fn read_toml(file: &Path) -> Result<toml::Value, ChgError> {
+    let text = std::fs::read_to_string(file)?;
+    toml::from_str(&text)
+        .map_err(|e| ChgError::ConfigReadError(format!("{}: {}", file.display(), e)))
+}
+
+/// Merges tables recursively; other values of `top` replace those of `bottom`.
+fn merge(bottom: &mut toml::Value, top: &toml::Value) {
+    match (bottom, top) {
+        (toml::Value::Table(bottom), toml::Value::Table(top)) => {
+            for (key, value) in top {
+                match bottom.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        bottom.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (bottom, top) => *bottom = top.clone(),
+    }
+}
+
+fn flatten(prefix: &str, value: &toml::Value, leaves: &mut Vec<(String, toml::Value)>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, leaves);
+            }
+        }
+        value => leaves.push((prefix.to_string(), value.clone())),
+    }
+}
+
+fn lookup<'a>(value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.')
+        .try_fold(value, |value, part| value.get(part))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::discovery::{ConfigLayers, ConfigSource};
+    use crate::test_util::TempDir;
+    use crate::ChangeLog;
+
+    #[test]
+    fn layers_precedence() {
+        let temp = TempDir::new("discovery");
+        let dir = temp.0.clone();
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"x\"\n[package.metadata.changelog.git]\ntraversal = \"topological\"\nyanked_marker = \"[YANKED]\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("chg.toml"),
+            "[git]\ntraversal = \"merges\"\n[keys]\ncommit_link = \"https://x/{commit}\"\n",
+        )
+        .unwrap();
+        let changelog_file = dir.join("CHANGELOG.md");
+        let text = "# Changelog\n<!-- CHANGELOG-CONFIG\n[keys]\ncommit_link = \"https://y/{commit}\"\n-->\n";
+
+        let layers = ConfigLayers::discover(&dir)
+            .unwrap()
+            .with_embedded(&changelog_file, text)
+            .unwrap();
+        let config = layers.config().unwrap();
+        assert_eq!(config.git.traversal, "merges");
+        assert_eq!(config.git.yanked_marker, "[YANKED]");
+        assert_eq!(config.keys.commit_link, "https://y/{commit}");
+
+        let provenance = layers.provenance().unwrap();
+        let source = |key: &str| {
+            provenance
+                .iter()
+                .find(|(k, _, _)| k == key)
+                .unwrap()
+                .2
+                .clone()
+        };
+        assert_eq!(
+            source("git.traversal"),
+            ConfigSource::File(dir.join("chg.toml"))
+        );
+        assert_eq!(
+            source("git.yanked_marker"),
+            ConfigSource::CargoMetadata(dir.join("Cargo.toml"))
+        );
+        assert_eq!(
+            source("keys.commit_link"),
+            ConfigSource::Embedded(PathBuf::from(&changelog_file))
+        );
+
+        // values from files are not copied into the changelog
+        std::fs::write(&changelog_file, text).unwrap();
+        let mut changelog = ChangeLog::load(&changelog_file, &dir).unwrap();
+        changelog.store_config().unwrap();
+        assert!(
+            changelog.prolog.contains("https://y/{commit}"),
+            "{}",
+            changelog.prolog
+        );
+        assert!(!changelog.prolog.contains("merges"), "{}", changelog.prolog);
+    }
+
+    #[test]
+    fn links_from_remote() {
+        let temp = TempDir::new("remote");
+        let dir = temp.0.clone();
+        let repo = git2::Repository::init(&dir).unwrap();
+        repo.remote("origin", "git@git.example.com:group/sub/repo.git")
+            .unwrap();
+        let changelog_file = dir.join("CHANGELOG.md");
+        let text = "<!-- CHANGELOG-CONFIG\n[keys]\nhosting = \"gitlab\"\nissue_link = \"https://jira.example.com/browse/X-{number}\"\n-->\n";
+
+        let layers = ConfigLayers::discover(&dir)
+            .unwrap()
+            .with_embedded(&changelog_file, text)
+            .unwrap();
+        let config = layers.config().unwrap();
+        assert_eq!(
+            config.keys.pr_link,
+            "https://git.example.com/group/sub/repo/-/merge_requests/{number}"
+        );
+        assert_eq!(
+            config.keys.issue_link,
+            "https://jira.example.com/browse/X-{number}"
+        );
+        let provenance = layers.provenance().unwrap();
+        let source = |key: &str| {
+            provenance
+                .iter()
+                .find(|(k, _, _)| k == key)
+                .unwrap()
+                .2
+                .clone()
+        };
+        assert_eq!(
+            source("keys.commit_link"),
+            ConfigSource::Remote("https://git.example.com/group/sub/repo".to_string())
+        );
+        assert_eq!(
+            source("keys.issue_link"),
+            ConfigSource::Embedded(changelog_file.clone())
+        );
+
+        // derived links are not written into the changelog
+        let embedded = config.to_string_embedded().unwrap();
+        assert!(!embedded.contains("merge_requests"), "{}", embedded);
+        assert!(embedded.contains("jira"), "{}", embedded);
+    }
+}
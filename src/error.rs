@@ -27,6 +27,65 @@ pub enum ChgError {
 
     #[error("Cannot serialize config")]
     ConfigWriteError,
+
+    #[error("Unknown change type ('{0}')")]
+    UnknownChangeType(String),
+
+    #[error("Invalid component path pattern ('{0}'): {1}")]
+    InvalidComponentPattern(String, String),
+
+    #[error("Invalid key pattern of tracker '{0}': {1}")]
+    InvalidTrackerKey(String, String),
+
+    #[error("{0} changelog item(s) need fixing")]
+    LintFailed(usize),
+
+    #[error("Cannot read PR/issue metadata: {0}")]
+    MetadataError(String),
+
+    #[error("Git error: {0}")]
+    GitError(git2::Error),
+
+    #[error(
+        "Unknown history traversal ('{0}'), expected one of: first-parent, topological, merges"
+    )]
+    UnknownTraversal(String),
+
+    #[error("Cannot sync: no common release between changelog and imported history")]
+    DisjointHistories,
+
+    #[error("Unknown export format ('{0}'), expected one of: markdown, html, atom, rss, debian, rpm, asciidoc, rst")]
+    UnknownExportFormat(String),
+
+    #[error("Unknown layout style ('{0}'), expected one of: default, grouped, keep-a-changelog")]
+    UnknownLayoutStyle(String),
+
+    #[error("Unknown hosting ('{0}'), expected one of: github, gitlab, bitbucket, gitea, azure")]
+    UnknownHosting(String),
+
+    #[error("Unknown trailer field ('{0}'), expected one of: text, details, type, component, refs, authors, skip, none")]
+    UnknownTrailerField(String),
+
+    #[error("Unknown release profile ('{0}'), expected one of: default, maven, cargo, npm, semantic-release")]
+    UnknownReleaseProfile(String),
+
+    #[error("Invalid release commit pattern '{0}': {1}")]
+    InvalidReleasePattern(String, String),
+
+    #[error("Cannot import from format '{0}'")]
+    UnsupportedImportFormat(String),
+
+    #[error("Invalid template ('{0}'): {1}")]
+    InvalidTemplate(String, String),
+
+    #[error("Missing config value '{0}'")]
+    MissingConfigValue(String),
+
+    #[error("Invalid Debian changelog line ('{0}')")]
+    InvalidDebianChangelog(String),
+
+    #[error("Cannot sync: latest changelog release '{0}' is not present in imported history")]
+    MissingLatestRelease(String),
 }
 
 impl From<std::io::Error> for ChgError {
@@ -34,3 +93,9 @@ impl From<std::io::Error> for ChgError {
         Self::IOError(e)
     }
 }
+
+impl From<git2::Error> for ChgError {
+    fn from(e: git2::Error) -> Self {
+        Self::GitError(e)
+    }
+}
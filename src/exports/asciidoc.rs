@@ -0,0 +1,179 @@
+use std::io::Write;
+
+use crate::api::{ChangeItem, ChangeSet};
+use crate::exports::{anchor, items_by_type, split_prolog};
+use crate::{ChangeLog, ChangeLogConfig};
+
+impl ChangeLog {
+    /// Renders the changelog as AsciiDoc document, like for Antora; items are listed per change type.
+    pub fn print_asciidoc(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let (title, prose) = split_prolog(&self.config.strip_embedded(&self.prolog));
+        writeln!(out, "= {}", escape(&title))?;
+        print_paragraphs(out, &prose)?;
+
+        if let Some(unreleased) = &self.unreleased {
+            writeln!(out)?;
+            writeln!(out, "[[unreleased]]")?;
+            writeln!(out, "== Unreleased")?;
+            print_changeset(out, unreleased, &self.config)?;
+        }
+        for (header, changeset) in &self.releases {
+            writeln!(out)?;
+            writeln!(out, "[[{}]]", anchor(header))?;
+            if header.yanked {
+                writeln!(
+                    out,
+                    "== [line-through]#{}# - {} (YANKED)",
+                    header.version, header.timestamp
+                )?;
+            } else {
+                writeln!(out, "== {} - {}", header.version, header.timestamp)?;
+            }
+            print_changeset(out, changeset, &self.config)?;
+        }
+        print_paragraphs(out, &self.config.strip_embedded(&self.epilog))?;
+        Ok(())
+    }
+}
+
+fn print_paragraphs(out: &mut dyn Write, text: &str) -> std::io::Result<()> {
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        writeln!(out)?;
+        writeln!(out, "{}", paragraph)?;
+    }
+    Ok(())
+}
+
+fn print_changeset(
+    out: &mut dyn Write,
+    changeset: &ChangeSet,
+    config: &ChangeLogConfig,
+) -> std::io::Result<()> {
+    if !changeset.notes.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "[quote]")?;
+        writeln!(out, "____")?;
+        writeln!(out, "{}", changeset.notes)?;
+        writeln!(out, "____")?;
+    }
+    for (change_type, items) in items_by_type(changeset) {
+        writeln!(out)?;
+        writeln!(out, "=== {}", change_type.title())?;
+        writeln!(out)?;
+        for item in items {
+            print_item(out, item, config)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_item(
+    out: &mut dyn Write,
+    item: &ChangeItem,
+    config: &ChangeLogConfig,
+) -> std::io::Result<()> {
+    write!(out, "* ")?;
+    let refs: Vec<String> = item
+        .refs
+        .iter()
+        .map(|reference| match config.ref_link(reference) {
+            None => reference.to_string(),
+            Some(link) => format!("{}[{}]", link, reference),
+        })
+        .collect();
+    if !refs.is_empty() {
+        write!(out, "{}: ", refs.join(", "))?;
+    }
+    if !item.component.is_empty() {
+        write!(out, "`{}` ", escape(&item.component))?;
+    }
+    write!(out, "{}", escape(&item.text))?;
+    let authors: Vec<String> = item
+        .authors
+        .iter()
+        .filter(|a| !a.is_empty())
+        .map(|a| escape(a))
+        .collect();
+    if !authors.is_empty() {
+        write!(out, " _{}_", authors.join(", "))?;
+    }
+    if let (Some(commit), Some(short)) = (&item.commit, item.short_commit()) {
+        match config.commit_link(commit) {
+            None => write!(out, " (`{}`)", short)?,
+            Some(link) => write!(out, " ({}[`{}`])", link, short)?,
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Escapes characters that start inline markup, or an attribute list.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '_' | '`' | '+' | '#' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::ChangeType;
+    use crate::ChangeLog;
+
+    const CHANGELOG: &str = r#"# Changelog
+Notable changes.
+
+<!-- CHANGELOG-CONFIG
+[keys]
+pr_link = "https://github.com/o/r/pull/{number}"
+-->
+
+## Unreleased
+
+- PR#12, #3: [core] Fix crash in *parse_line* / pk
+
+## 1.0.1 - 2020-09-15 [YANKED]
+
+> Hotfix release.
+
+- Fix NPE / pk
+"#;
+
+    #[test]
+    fn asciidoc() {
+        let mut changelog = ChangeLog::import_markdown(CHANGELOG).unwrap();
+        changelog.releases[0].1.items[0].change_type = ChangeType::Fixed;
+        let mut out = Vec::new();
+        changelog.print_asciidoc(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"= Changelog
+
+Notable changes.
+
+[[unreleased]]
+== Unreleased
+
+=== Other
+
+* https://github.com/o/r/pull/12[PR#12], #3: `core` Fix crash in \*parse\_line\* _pk_
+
+[[v1.0.1]]
+== [line-through]#1.0.1# - 2020-09-15 (YANKED)
+
+[quote]
+____
+Hotfix release.
+____
+
+=== Fixed
+
+* Fix NPE _pk_
+"#
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use std::io::Write;
+
+use crate::api::ReleaseHeader;
+use crate::exports::{or_default, plain_item_text, print_wrapped};
+use crate::{ChangeLog, ChgError};
+
+impl ChangeLog {
+    /// Renders releases in `debian/changelog` format; constant fields come from the `[debian]` config section.
+    ///
+    /// Pre-release versions like `1.0.0-rc1` are written as `1.0.0~rc1`, so that they sort before the release.
+    pub fn print_debian(&self, out: &mut dyn Write) -> Result<(), ChgError> {
+        let debian = &self.config.debian;
+        if debian.package.is_empty() {
+            return Err(ChgError::MissingConfigValue("debian.package".to_string()));
+        }
+        if debian.maintainer.is_empty() {
+            return Err(ChgError::MissingConfigValue(
+                "debian.maintainer".to_string(),
+            ));
+        }
+        let distribution = or_default(&debian.distribution, "unstable");
+        let urgency = or_default(&debian.urgency, "medium");
+        let revision = or_default(&debian.revision, "1");
+        for (header, changeset) in &self.releases {
+            writeln!(
+                out,
+                "{} ({}-{}) {}; urgency={}",
+                debian.package,
+                header.version.replace('-', "~"),
+                revision,
+                distribution,
+                urgency
+            )?;
+            writeln!(out)?;
+            for line in changeset.notes.lines() {
+                writeln!(out, "  {}", line)?;
+            }
+            if !changeset.notes.is_empty() {
+                writeln!(out)?;
+            }
+            for item in &changeset.items {
+                print_wrapped(out, "  * ", "    ", &plain_item_text(item))?;
+            }
+            if !changeset.items.is_empty() {
+                writeln!(out)?;
+            }
+            writeln!(out, " -- {}  {}", debian.maintainer, rfc2822(header))?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+fn rfc2822(header: &ReleaseHeader) -> String {
+    header
+        .timestamp
+        .format("%a, %d %b %Y 00:00:00 +0000")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChangeLog;
+
+    const CHANGELOG: &str = r#"# Changelog
+
+<!-- CHANGELOG-CONFIG
+[debian]
+package = "mojo"
+maintainer = "Jane Doe <jane@example.com>"
+-->
+
+## 1.1.0-rc1 - 2020-10-01
+
+> Preview of the parser.
+
+- PR#12, #3: [core] Add parser, which is a very long description of the change that needs wrapping / pk
+
+## 1.0.1 - 2020-09-15
+
+- Fix NPE / pk
+"#;
+
+    const DEBIAN: &str = "mojo (1.1.0~rc1-1) unstable; urgency=medium
+
+  Preview of the parser.
+
+  * [core] Add parser, which is a very long description of the change that
+    needs wrapping (PR#12, #3)
+
+ -- Jane Doe <jane@example.com>  Thu, 01 Oct 2020 00:00:00 +0000
+
+mojo (1.0.1-1) unstable; urgency=medium
+
+  * Fix NPE
+
+ -- Jane Doe <jane@example.com>  Tue, 15 Sep 2020 00:00:00 +0000
+
+";
+
+    #[test]
+    fn debian_export() {
+        let changelog = ChangeLog::import_markdown(CHANGELOG).unwrap();
+        let mut out = Vec::new();
+        changelog.print_debian(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), DEBIAN);
+    }
+
+    #[test]
+    fn debian_roundtrip() {
+        let changelog = ChangeLog::import_markdown(CHANGELOG).unwrap();
+        let imported = ChangeLog::import_debian(DEBIAN, changelog.config.clone()).unwrap();
+        let mut out = Vec::new();
+        imported.print_debian(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), DEBIAN);
+        assert_eq!(imported.releases[0].0.version, "1.1.0-rc1");
+        let item = &imported.releases[0].1.items[0];
+        assert_eq!(item.refs, vec!["PR#12", "#3"]);
+        assert_eq!(item.component, "core");
+        assert_eq!(item.authors, vec!["Jane Doe"]);
+    }
+}
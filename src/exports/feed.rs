@@ -0,0 +1,250 @@
+use std::io::Write;
+
+use crate::api::{ChangeSet, ReleaseHeader};
+use crate::exports::html::{escape, print_changeset};
+use crate::exports::{anchor, split_prolog};
+use crate::{ChangeLog, ChgError};
+
+impl ChangeLog {
+    /// Renders releases as Atom feed, one entry per release.
+    ///
+    /// Output depends on the changelog content only, so that it can be committed and diffed:
+    /// the feed is as recent as its latest release, or dated to the epoch without releases.
+    pub fn print_atom(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+        writeln!(out, "<feed xmlns=\"http://www.w3.org/2005/Atom\">")?;
+        writeln!(out, "  <title>{}</title>", escape(&self.feed_title()))?;
+        let link = &self.config.feed.link;
+        if link.is_empty() {
+            writeln!(out, "  <id>{}</id>", escape(&self.feed_urn()))?;
+        } else {
+            writeln!(out, "  <id>{}</id>", escape(link))?;
+            writeln!(out, "  <link href=\"{}\"/>", escape(link))?;
+        }
+        let updated = match self.releases.first() {
+            Some((latest, _)) => format!("{}T00:00:00Z", latest.timestamp),
+            None => "1970-01-01T00:00:00Z".to_string(),
+        };
+        writeln!(out, "  <updated>{}</updated>", updated)?;
+        writeln!(
+            out,
+            "  <author><name>{}</name></author>",
+            escape(&self.feed_title())
+        )?;
+        for (header, changeset) in &self.releases {
+            writeln!(out, "  <entry>")?;
+            writeln!(out, "    <title>{}</title>", escape(&entry_title(header)))?;
+            match self.permalink(header) {
+                None => writeln!(
+                    out,
+                    "    <id>{}:{}</id>",
+                    escape(&self.feed_urn()),
+                    escape(&header.version)
+                )?,
+                Some(permalink) => {
+                    writeln!(out, "    <id>{}</id>", escape(&permalink))?;
+                    writeln!(out, "    <link href=\"{}\"/>", escape(&permalink))?;
+                }
+            }
+            writeln!(out, "    <updated>{}T00:00:00Z</updated>", header.timestamp)?;
+            writeln!(
+                out,
+                "    <content type=\"html\">{}</content>",
+                escape(&self.changeset_html(changeset)?)
+            )?;
+            writeln!(out, "  </entry>")?;
+        }
+        writeln!(out, "</feed>")?;
+        Ok(())
+    }
+
+    /// Renders releases as RSS 2.0 feed, one item per release; the channel needs `link` from the `[feed]` config section.
+    pub fn print_rss(&self, out: &mut dyn Write) -> Result<(), ChgError> {
+        let link = &self.config.feed.link;
+        if link.is_empty() {
+            return Err(ChgError::MissingConfigValue("feed.link".to_string()));
+        }
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+        writeln!(out, "<rss version=\"2.0\">")?;
+        writeln!(out, "  <channel>")?;
+        writeln!(out, "    <title>{}</title>", escape(&self.feed_title()))?;
+        writeln!(out, "    <link>{}</link>", escape(link))?;
+        writeln!(
+            out,
+            "    <description>Releases of {}</description>",
+            escape(&self.feed_title())
+        )?;
+        if let Some((latest, _)) = self.releases.first() {
+            writeln!(out, "    <lastBuildDate>{}</lastBuildDate>", rfc822(latest))?;
+        }
+        for (header, changeset) in &self.releases {
+            writeln!(out, "    <item>")?;
+            writeln!(out, "      <title>{}</title>", escape(&entry_title(header)))?;
+            match self.permalink(header) {
+                None => writeln!(
+                    out,
+                    "      <guid isPermaLink=\"false\">{}</guid>",
+                    escape(&header.version)
+                )?,
+                Some(permalink) => {
+                    writeln!(out, "      <link>{}</link>", escape(&permalink))?;
+                    writeln!(out, "      <guid>{}</guid>", escape(&permalink))?;
+                }
+            }
+            writeln!(out, "      <pubDate>{}</pubDate>", rfc822(header))?;
+            writeln!(
+                out,
+                "      <description>{}</description>",
+                escape(&self.changeset_html(changeset)?)
+            )?;
+            writeln!(out, "    </item>")?;
+        }
+        writeln!(out, "  </channel>")?;
+        writeln!(out, "</rss>")?;
+        Ok(())
+    }
+
+    fn feed_title(&self) -> String {
+        let feed = &self.config.feed;
+        if feed.title.is_empty() {
+            split_prolog(&self.config.strip_embedded(&self.prolog)).0
+        } else {
+            feed.title.clone()
+        }
+    }
+
+    /// Id of a feed without link, like `urn:changelog:mojo-changes`.
+    fn feed_urn(&self) -> String {
+        let mut slug = String::new();
+        for c in self.feed_title().chars() {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+                slug.push(c.to_ascii_lowercase());
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        format!("urn:changelog:{}", slug.trim_end_matches('-'))
+    }
+
+    /// Link to the release, per `permalink` template or `link` with anchor.
+    fn permalink(&self, header: &ReleaseHeader) -> Option<String> {
+        let feed = &self.config.feed;
+        if !feed.permalink.is_empty() {
+            Some(feed.permalink.replace("{version}", &header.version))
+        } else if !feed.link.is_empty() {
+            Some(format!("{}#{}", feed.link, anchor(header)))
+        } else {
+            None
+        }
+    }
+
+    fn changeset_html(&self, changeset: &ChangeSet) -> std::io::Result<String> {
+        let mut html = Vec::new();
+        print_changeset(&mut html, changeset, &self.config)?;
+        Ok(String::from_utf8_lossy(&html).into_owned())
+    }
+}
+
+fn entry_title(header: &ReleaseHeader) -> String {
+    if header.yanked {
+        format!("{} [YANKED]", header.version)
+    } else {
+        header.version.clone()
+    }
+}
+
+fn rfc822(header: &ReleaseHeader) -> String {
+    header
+        .timestamp
+        .format("%a, %d %b %Y 00:00:00 +0000")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChangeLog;
+
+    const CHANGELOG: &str = r#"# Mojo changes
+
+<!-- CHANGELOG-CONFIG
+[feed]
+link = "https://example.com/changelog.html"
+-->
+
+## Unreleased
+
+- Work in progress / pk
+
+## 1.1.0 - 2020-10-01
+
+- Add parser / pk
+
+## 1.0.1 - 2020-09-15 [YANKED]
+
+- Fix <NPE> / pk
+"#;
+
+    #[test]
+    fn atom() {
+        let changelog = ChangeLog::import_markdown(CHANGELOG).unwrap();
+        let mut out = Vec::new();
+        changelog.print_atom(&mut out).unwrap();
+        let atom = String::from_utf8(out).unwrap();
+        assert!(atom.contains("<title>Mojo changes</title>"), "{}", atom);
+        assert!(
+            atom.contains("<updated>2020-10-01T00:00:00Z</updated>"),
+            "{}",
+            atom
+        );
+        assert_eq!(atom.matches("<entry>").count(), 2, "{}", atom);
+        assert!(!atom.contains("Work in progress"), "{}", atom);
+        assert!(
+            atom.contains("<id>https://example.com/changelog.html#v1.0.1</id>"),
+            "{}",
+            atom
+        );
+        assert!(atom.contains("<title>1.0.1 [YANKED]</title>"), "{}", atom);
+        assert!(
+            atom.contains("&lt;li&gt;Fix &amp;lt;NPE&amp;gt;"),
+            "{}",
+            atom
+        );
+    }
+
+    #[test]
+    fn rss() {
+        let changelog = ChangeLog::import_markdown(CHANGELOG).unwrap();
+        let mut out = Vec::new();
+        changelog.print_rss(&mut out).unwrap();
+        let rss = String::from_utf8(out).unwrap();
+        assert!(
+            rss.contains("<pubDate>Thu, 01 Oct 2020 00:00:00 +0000</pubDate>"),
+            "{}",
+            rss
+        );
+        assert!(
+            rss.contains("<guid>https://example.com/changelog.html#v1.1.0</guid>"),
+            "{}",
+            rss
+        );
+    }
+
+    #[test]
+    fn atom_without_link() {
+        let changelog = ChangeLog::import_markdown("# Mojo changes!\n\n## Unreleased\n").unwrap();
+        let mut out = Vec::new();
+        changelog.print_atom(&mut out).unwrap();
+        let atom = String::from_utf8(out).unwrap();
+        assert!(
+            atom.contains("<id>urn:changelog:mojo-changes</id>"),
+            "{}",
+            atom
+        );
+        assert!(
+            atom.contains("<updated>1970-01-01T00:00:00Z</updated>"),
+            "{}",
+            atom
+        );
+        assert!(changelog.print_rss(&mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use std::io::Write;
+
+use crate::api::{ChangeItem, ChangeSet, ChangeType};
+use crate::exports::{anchor, split_prolog};
+use crate::{ChangeLog, ChangeLogConfig};
+
+const STYLE: &str = r#"body { font-family: sans-serif; max-width: 50em; margin: auto; padding: 1em; line-height: 1.5; }
+nav.toc ul { list-style: none; padding-left: 0; }
+nav.toc li { display: inline-block; margin-right: 1em; }
+section.release > h2 { border-bottom: 1px solid #ddd; }
+section.yanked > h2 .version { text-decoration: line-through; }
+.yanked-label { color: #b00; font-size: 0.7em; vertical-align: middle; }
+blockquote { color: #555; border-left: 3px solid #ddd; margin-left: 0; padding-left: 1em; }
+.badge { display: inline-block; min-width: 6em; padding: 0 0.4em; border-radius: 0.3em; font-size: 0.8em; text-align: center; color: #fff; background: #777; }
+.badge-added { background: #2a7d2a; }
+.badge-changed { background: #1f5fa8; }
+.badge-deprecated { background: #b8860b; }
+.badge-removed { background: #a33; }
+.badge-fixed { background: #6a3d9a; }
+.badge-refactored { background: #2b8a8a; }
+.component { font-family: monospace; }
+.authors, .commit { color: #777; }
+"#;
+
+impl ChangeLog {
+    /// Renders the changelog as HTML; either a fragment to embed in a page, or a complete page with inline styles.
+    pub fn print_html(&self, out: &mut dyn Write, standalone: bool) -> std::io::Result<()> {
+        let (title, prose) = split_prolog(&self.config.strip_embedded(&self.prolog));
+        if standalone {
+            writeln!(out, "<!DOCTYPE html>")?;
+            writeln!(out, "<html>")?;
+            writeln!(out, "<head>")?;
+            writeln!(out, "<meta charset=\"utf-8\">")?;
+            writeln!(out, "<title>{}</title>", escape(&title))?;
+            writeln!(out, "<style>\n{}</style>", STYLE)?;
+            writeln!(out, "</head>")?;
+            writeln!(out, "<body>")?;
+        }
+        writeln!(out, "<div class=\"changelog\">")?;
+        writeln!(out, "<h1>{}</h1>", escape(&title))?;
+        print_paragraphs(out, &prose)?;
+
+        // table of contents
+        writeln!(out, "<nav class=\"toc\">")?;
+        writeln!(out, "<ul>")?;
+        if self.unreleased.is_some() {
+            writeln!(out, "<li><a href=\"#unreleased\">Unreleased</a></li>")?;
+        }
+        for (header, _) in &self.releases {
+            writeln!(
+                out,
+                "<li><a href=\"#{}\">{}</a></li>",
+                anchor(header),
+                escape(&header.version)
+            )?;
+        }
+        writeln!(out, "</ul>")?;
+        writeln!(out, "</nav>")?;
+
+        if let Some(unreleased) = &self.unreleased {
+            writeln!(out, "<section class=\"release\" id=\"unreleased\">")?;
+            writeln!(out, "<h2>Unreleased</h2>")?;
+            print_changeset(out, unreleased, &self.config)?;
+            writeln!(out, "</section>")?;
+        }
+        for (header, changeset) in &self.releases {
+            let class = if header.yanked {
+                "release yanked"
+            } else {
+                "release"
+            };
+            writeln!(
+                out,
+                "<section class=\"{}\" id=\"{}\">",
+                class,
+                anchor(header)
+            )?;
+            write!(
+                out,
+                "<h2><span class=\"version\">{}</span> - <time>{}</time>",
+                escape(&header.version),
+                header.timestamp
+            )?;
+            if header.yanked {
+                write!(out, " <span class=\"yanked-label\">YANKED</span>")?;
+            }
+            writeln!(out, "</h2>")?;
+            print_changeset(out, changeset, &self.config)?;
+            writeln!(out, "</section>")?;
+        }
+
+        print_paragraphs(out, &self.config.strip_embedded(&self.epilog))?;
+        writeln!(out, "</div>")?;
+        if standalone {
+            writeln!(out, "</body>")?;
+            writeln!(out, "</html>")?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints text as paragraphs separated by empty lines; no other markup is interpreted.
+fn print_paragraphs(out: &mut dyn Write, text: &str) -> std::io::Result<()> {
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        writeln!(out, "<p>{}</p>", escape(paragraph))?;
+    }
+    Ok(())
+}
+
+pub(super) fn print_changeset(
+    out: &mut dyn Write,
+    changeset: &ChangeSet,
+    config: &ChangeLogConfig,
+) -> std::io::Result<()> {
+    if !changeset.notes.is_empty() {
+        writeln!(out, "<blockquote>")?;
+        print_paragraphs(out, &changeset.notes)?;
+        writeln!(out, "</blockquote>")?;
+    }
+    if changeset.items.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "<ul>")?;
+    for item in &changeset.items {
+        print_item(out, item, config)?;
+    }
+    writeln!(out, "</ul>")?;
+    Ok(())
+}
+
+fn print_item(
+    out: &mut dyn Write,
+    item: &ChangeItem,
+    config: &ChangeLogConfig,
+) -> std::io::Result<()> {
+    write!(out, "<li>")?;
+    if item.change_type != ChangeType::Other {
+        write!(
+            out,
+            "<span class=\"badge badge-{}\">{}</span> ",
+            item.change_type.as_str(),
+            item.change_type.title()
+        )?;
+    }
+    for (i, reference) in item.refs.iter().enumerate() {
+        if i > 0 {
+            write!(out, ", ")?;
+        }
+        let text = reference.to_string();
+        match config.ref_link(reference) {
+            None => write!(out, "{}", escape(&text))?,
+            Some(link) => write!(out, "<a href=\"{}\">{}</a>", escape(&link), escape(&text))?,
+        }
+    }
+    if !item.refs.is_empty() {
+        write!(out, ": ")?;
+    }
+    if !item.component.is_empty() {
+        write!(
+            out,
+            "<span class=\"component\">[{}]</span> ",
+            escape(&item.component)
+        )?;
+    }
+    write!(out, "{}", escape(&item.text))?;
+    let authors: Vec<&String> = item.authors.iter().filter(|a| !a.is_empty()).collect();
+    if !authors.is_empty() {
+        let authors: Vec<String> = authors.iter().map(|a| escape(a)).collect();
+        write!(
+            out,
+            " <span class=\"authors\">/ {}</span>",
+            authors.join(", ")
+        )?;
+    }
+    if let (Some(commit), Some(short)) = (&item.commit, item.short_commit()) {
+        match config.commit_link(commit) {
+            None => write!(out, " <code class=\"commit\">{}</code>", short)?,
+            Some(link) => write!(
+                out,
+                " <a class=\"commit\" href=\"{}\"><code>{}</code></a>",
+                escape(&link),
+                short
+            )?,
+        }
+    }
+    writeln!(out, "</li>")?;
+    Ok(())
+}
+
+pub(super) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChangeLog;
+
+    const CHANGELOG: &str = r#"# Changelog
+Notable changes of <this> project.
+
+<!-- CHANGELOG-CONFIG
+[keys]
+pr_link = "https://github.com/o/r/pull/{number}"
+-->
+
+## Unreleased
+
+- PR#12, #3: [core] Fix crash / pk
+
+## 1.0.1 - 2020-09-15 [YANKED]
+
+> Hotfix release.
+
+- Fix NPE / pk
+"#;
+
+    #[test]
+    fn html_page() {
+        let changelog = ChangeLog::import_markdown(CHANGELOG).unwrap();
+        let mut out = Vec::new();
+        changelog.print_html(&mut out, true).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"), "{}", html);
+        assert!(html.contains("<title>Changelog</title>"), "{}", html);
+        assert!(
+            html.contains("<p>Notable changes of &lt;this&gt; project.</p>"),
+            "{}",
+            html
+        );
+        assert!(!html.contains("CHANGELOG-CONFIG"), "{}", html);
+        assert!(
+            html.contains("<li><a href=\"#v1.0.1\">1.0.1</a></li>"),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("<a href=\"https://github.com/o/r/pull/12\">PR#12</a>, #3: <span class=\"component\">[core]</span> Fix crash"),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("<section class=\"release yanked\" id=\"v1.0.1\">"),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("<blockquote>\n<p>Hotfix release.</p>\n</blockquote>"),
+            "{}",
+            html
+        );
+
+        let mut out = Vec::new();
+        changelog.print_html(&mut out, false).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.starts_with("<div class=\"changelog\">"), "{}", html);
+    }
+}
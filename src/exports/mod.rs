@@ -0,0 +1,225 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::api::{ChangeItem, ChangeSet, ChangeType, Reference, ReleaseHeader};
+use crate::{ChangeLog, ChgError};
+
+pub mod asciidoc;
+pub mod debian;
+pub mod feed;
+pub mod html;
+pub mod rpm;
+pub mod rst;
+
+/// Writes the changelog in some format.
+pub trait Renderer {
+    fn render(&self, changelog: &ChangeLog, out: &mut dyn Write) -> Result<(), ChgError>;
+}
+
+pub struct MarkdownRenderer;
+
+impl Renderer for MarkdownRenderer {
+    fn render(&self, changelog: &ChangeLog, out: &mut dyn Write) -> Result<(), ChgError> {
+        Ok(changelog.print_markdown(out)?)
+    }
+}
+
+pub struct HtmlRenderer {
+    /// complete page rather than a fragment
+    pub standalone: bool,
+}
+
+impl Renderer for HtmlRenderer {
+    fn render(&self, changelog: &ChangeLog, out: &mut dyn Write) -> Result<(), ChgError> {
+        Ok(changelog.print_html(out, self.standalone)?)
+    }
+}
+
+pub struct AtomRenderer;
+
+impl Renderer for AtomRenderer {
+    fn render(&self, changelog: &ChangeLog, out: &mut dyn Write) -> Result<(), ChgError> {
+        Ok(changelog.print_atom(out)?)
+    }
+}
+
+pub struct RssRenderer;
+
+impl Renderer for RssRenderer {
+    fn render(&self, changelog: &ChangeLog, out: &mut dyn Write) -> Result<(), ChgError> {
+        changelog.print_rss(out)
+    }
+}
+
+pub struct DebianRenderer;
+
+impl Renderer for DebianRenderer {
+    fn render(&self, changelog: &ChangeLog, out: &mut dyn Write) -> Result<(), ChgError> {
+        changelog.print_debian(out)
+    }
+}
+
+pub struct RpmRenderer;
+
+impl Renderer for RpmRenderer {
+    fn render(&self, changelog: &ChangeLog, out: &mut dyn Write) -> Result<(), ChgError> {
+        changelog.print_rpm(out)
+    }
+}
+
+pub struct AsciiDocRenderer;
+
+impl Renderer for AsciiDocRenderer {
+    fn render(&self, changelog: &ChangeLog, out: &mut dyn Write) -> Result<(), ChgError> {
+        Ok(changelog.print_asciidoc(out)?)
+    }
+}
+
+pub struct RstRenderer;
+
+impl Renderer for RstRenderer {
+    fn render(&self, changelog: &ChangeLog, out: &mut dyn Write) -> Result<(), ChgError> {
+        Ok(changelog.print_rst(out)?)
+    }
+}
+
+/// Changelog file format, for `chg export` and `chg merge`.
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Atom,
+    Rss,
+    Debian,
+    Rpm,
+    AsciiDoc,
+    Rst,
+}
+
+impl ExportFormat {
+    /// Renderer of this format; `fragment` asks for output to embed in a page, where it applies.
+    pub fn renderer(self, fragment: bool) -> Box<dyn Renderer> {
+        match self {
+            ExportFormat::Markdown => Box::new(MarkdownRenderer),
+            ExportFormat::Html => Box::new(HtmlRenderer {
+                standalone: !fragment,
+            }),
+            ExportFormat::Atom => Box::new(AtomRenderer),
+            ExportFormat::Rss => Box::new(RssRenderer),
+            ExportFormat::Debian => Box::new(DebianRenderer),
+            ExportFormat::Rpm => Box::new(RpmRenderer),
+            ExportFormat::AsciiDoc => Box::new(AsciiDocRenderer),
+            ExportFormat::Rst => Box::new(RstRenderer),
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = ChgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Ok(ExportFormat::Markdown),
+            "html" => Ok(ExportFormat::Html),
+            "atom" => Ok(ExportFormat::Atom),
+            "rss" => Ok(ExportFormat::Rss),
+            "debian" | "deb" => Ok(ExportFormat::Debian),
+            "rpm" => Ok(ExportFormat::Rpm),
+            "asciidoc" | "adoc" => Ok(ExportFormat::AsciiDoc),
+            "rst" | "restructuredtext" => Ok(ExportFormat::Rst),
+            _ => Err(ChgError::UnknownExportFormat(s.to_string())),
+        }
+    }
+}
+
+/// Maximal line width in packaging changelogs.
+const LINE_WIDTH: usize = 79;
+
+fn or_default<'a>(value: &'a str, default: &'a str) -> &'a str {
+    if value.is_empty() {
+        default
+    } else {
+        value
+    }
+}
+
+/// Item as `[component] text (refs)`, for formats without markup.
+fn plain_item_text(item: &ChangeItem) -> String {
+    let mut text = String::new();
+    if !item.component.is_empty() {
+        text.push_str(&format!("[{}] ", item.component));
+    }
+    text.push_str(&item.text);
+    if !item.refs.is_empty() {
+        text.push_str(&format!(" ({})", Reference::join(&item.refs)));
+    }
+    text
+}
+
+/// Prints the text after `bullet`, wrapping words to lines starting with `indent`.
+fn print_wrapped(
+    out: &mut dyn Write,
+    bullet: &str,
+    indent: &str,
+    text: &str,
+) -> std::io::Result<()> {
+    let mut line = bullet.to_string();
+    let mut line_start = true;
+    for word in text.split_whitespace() {
+        if !line_start && line.len() + 1 + word.len() > LINE_WIDTH {
+            writeln!(out, "{}", line)?;
+            line = indent.to_string();
+            line_start = true;
+        }
+        if !line_start {
+            line.push(' ');
+        }
+        line.push_str(word);
+        line_start = false;
+    }
+    writeln!(out, "{}", line)
+}
+
+/// Items grouped by change type, in the order of [`ChangeType::ALL`]; `Other` comes last.
+pub(crate) fn items_by_type(changeset: &ChangeSet) -> Vec<(ChangeType, Vec<&ChangeItem>)> {
+    ChangeType::ALL
+        .iter()
+        .map(|change_type| {
+            let items = changeset
+                .items
+                .iter()
+                .filter(|item| item.change_type == *change_type)
+                .collect::<Vec<_>>();
+            (*change_type, items)
+        })
+        .filter(|(_, items)| !items.is_empty())
+        .collect()
+}
+
+/// Anchor of the release section, like `v1.2.3`.
+fn anchor(header: &ReleaseHeader) -> String {
+    let version: String = header
+        .version
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("v{}", version)
+}
+
+/// Separates the leading `# Title` from the rest of prolog.
+fn split_prolog(prolog: &str) -> (String, String) {
+    let prolog = prolog.trim();
+    match prolog.strip_prefix("# ") {
+        None => ("Changelog".to_string(), prolog.to_string()),
+        Some(rest) => {
+            let (title, prose) = rest.split_at(rest.find('\n').unwrap_or(rest.len()));
+            (title.trim().to_string(), prose.trim().to_string())
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::io::Write;
+
+use crate::api::ReleaseHeader;
+use crate::exports::{or_default, plain_item_text, print_wrapped};
+use crate::{ChangeLog, ChgError};
+
+const CHANGELOG_SECTION: &str = "%changelog";
+
+/// Spec sections other than triggers, which come in many variants.
+const SECTIONS: &[&str] = &[
+    "package",
+    "description",
+    "prep",
+    "generate_buildrequires",
+    "conf",
+    "build",
+    "install",
+    "check",
+    "clean",
+    "files",
+    "changelog",
+    "pre",
+    "post",
+    "preun",
+    "postun",
+    "pretrans",
+    "posttrans",
+    "preuntrans",
+    "postuntrans",
+    "verifyscript",
+    "sourcelist",
+    "patchlist",
+];
+
+impl ChangeLog {
+    /// Renders releases as the body of RPM spec `%changelog` section; packager and release come from the `[rpm]` config
+    /// section.
+    ///
+    /// Pre-release versions like `1.0.0-rc1` are written as `1.0.0~rc1`, so that they sort before the release.
+    pub fn print_rpm(&self, out: &mut dyn Write) -> Result<(), ChgError> {
+        let rpm = &self.config.rpm;
+        if rpm.packager.is_empty() {
+            return Err(ChgError::MissingConfigValue("rpm.packager".to_string()));
+        }
+        let release = or_default(&rpm.release, "1");
+        for (i, (header, changeset)) in self.releases.iter().enumerate() {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            writeln!(
+                out,
+                "* {} {} - {}-{}",
+                rpm_date(header),
+                rpm.packager,
+                header.version.replace('-', "~"),
+                release
+            )?;
+            for item in &changeset.items {
+                // `%` starts a macro in spec files
+                print_wrapped(out, "- ", "  ", &plain_item_text(item).replace('%', "%%"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the `%changelog` section of the spec file content; the section is appended if missing.
+    pub fn replace_rpm_changelog(&self, spec: &str) -> Result<String, ChgError> {
+        let mut rendered = Vec::new();
+        self.print_rpm(&mut rendered)?;
+        let rendered = String::from_utf8_lossy(&rendered);
+
+        let mut result = String::new();
+        let mut lines = spec.lines();
+        let mut found = false;
+        for line in &mut lines {
+            result.push_str(line);
+            result.push('\n');
+            if line.trim_end() == CHANGELOG_SECTION {
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            if !result.is_empty() && !result.ends_with("\n\n") {
+                result.push('\n');
+            }
+            result.push_str(CHANGELOG_SECTION);
+            result.push('\n');
+        }
+        result.push_str(&rendered);
+        // the section ends with the next section, if any
+        let rest: Vec<&str> = lines.skip_while(|line| !is_section_start(line)).collect();
+        if !rest.is_empty() {
+            result.push('\n');
+            for line in rest {
+                result.push_str(line);
+                result.push('\n');
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Spec section starts like `%files` or `%package devel`; other macros like `%if` or `%{?dist}` do not end a section.
+fn is_section_start(line: &str) -> bool {
+    let name = match line.strip_prefix('%') {
+        None => return false,
+        Some(rest) => rest.split_whitespace().next().unwrap_or(""),
+    };
+    SECTIONS.contains(&name)
+        || ["trigger", "filetrigger", "transfiletrigger"]
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+fn rpm_date(header: &ReleaseHeader) -> String {
+    header.timestamp.format("%a %b %d %Y").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChangeLog;
+
+    const CHANGELOG: &str = r#"# Changelog
+
+<!-- CHANGELOG-CONFIG
+[rpm]
+packager = "Jane Doe <jane@example.com>"
+-->
+
+## Unreleased
+
+- Work in progress / pk
+
+## 1.1.0-rc1 - 2020-10-01
+
+- PR#12, #3: [core] Add parser, which is a very long description of the change that needs wrapping / pk
+
+## 1.0.1 - 2020-09-15
+
+- Fix 100% CPU load / pk
+"#;
+
+    const RPM: &str = "* Thu Oct 01 2020 Jane Doe <jane@example.com> - 1.1.0~rc1-1
+- [core] Add parser, which is a very long description of the change that needs
+  wrapping (PR#12, #3)
+
+* Tue Sep 15 2020 Jane Doe <jane@example.com> - 1.0.1-1
+- Fix 100%% CPU load
+";
+
+    #[test]
+    fn rpm_export() {
+        let changelog = ChangeLog::import_markdown(CHANGELOG).unwrap();
+        let mut out = Vec::new();
+        changelog.print_rpm(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), RPM);
+    }
+
+    #[test]
+    fn rpm_spec_replace() {
+        let changelog = ChangeLog::import_markdown(CHANGELOG).unwrap();
+        let spec = "Name: mojo\n\n%changelog\n* Mon Sep 14 2020 Old Entry - 1.0.0-1\n- Old\n\n%files\n/usr/bin/mojo\n";
+        assert_eq!(
+            changelog.replace_rpm_changelog(spec).unwrap(),
+            format!("Name: mojo\n\n%changelog\n{}\n%files\n/usr/bin/mojo\n", RPM)
+        );
+
+        // conditionals and macros inside the section are part of it
+        let spec = "Name: mojo\n\n%changelog\n%if 0%{?rhel}\n* Old\n%endif\n%{?changelog_extra}\n\n%package devel\n";
+        assert_eq!(
+            changelog.replace_rpm_changelog(spec).unwrap(),
+            format!("Name: mojo\n\n%changelog\n{}\n%package devel\n", RPM)
+        );
+
+        let spec = "Name: mojo\n";
+        assert_eq!(
+            changelog.replace_rpm_changelog(spec).unwrap(),
+            format!("Name: mojo\n\n%changelog\n{}", RPM)
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::io::Write;
+
+use crate::api::{ChangeItem, ChangeSet};
+use crate::exports::{anchor, items_by_type, split_prolog};
+use crate::{ChangeLog, ChangeLogConfig};
+
+impl ChangeLog {
+    /// Renders the changelog as reStructuredText document, like for Sphinx; items are listed per change type.
+    pub fn print_rst(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let (title, prose) = split_prolog(&self.config.strip_embedded(&self.prolog));
+        let title = escape(&title);
+        let line = "=".repeat(title.chars().count());
+        writeln!(out, "{}\n{}\n{}", line, title, line)?;
+        print_paragraphs(out, &prose, "")?;
+
+        if let Some(unreleased) = &self.unreleased {
+            writeln!(out)?;
+            writeln!(out, ".. _unreleased:")?;
+            print_heading(out, "Unreleased", '-')?;
+            print_changeset(out, unreleased, &self.config)?;
+        }
+        for (header, changeset) in &self.releases {
+            writeln!(out)?;
+            writeln!(out, ".. _{}:", anchor(header))?;
+            let heading = format!(
+                "{} - {}{}",
+                header.version,
+                header.timestamp,
+                if header.yanked { " (YANKED)" } else { "" }
+            );
+            print_heading(out, &heading, '-')?;
+            print_changeset(out, changeset, &self.config)?;
+        }
+        print_paragraphs(out, &self.config.strip_embedded(&self.epilog), "")?;
+        Ok(())
+    }
+}
+
+fn print_heading(out: &mut dyn Write, heading: &str, underline: char) -> std::io::Result<()> {
+    let heading = escape(heading);
+    writeln!(out)?;
+    writeln!(out, "{}", heading)?;
+    writeln!(
+        out,
+        "{}",
+        underline.to_string().repeat(heading.chars().count())
+    )
+}
+
+/// Prints paragraphs; indented ones form a block quote.
+fn print_paragraphs(out: &mut dyn Write, text: &str, indent: &str) -> std::io::Result<()> {
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        writeln!(out)?;
+        for line in paragraph.lines() {
+            writeln!(out, "{}{}", indent, escape(line.trim()))?;
+        }
+    }
+    Ok(())
+}
+
+fn print_changeset(
+    out: &mut dyn Write,
+    changeset: &ChangeSet,
+    config: &ChangeLogConfig,
+) -> std::io::Result<()> {
+    print_paragraphs(out, &changeset.notes, "   ")?;
+    for (change_type, items) in items_by_type(changeset) {
+        print_heading(out, change_type.title(), '~')?;
+        writeln!(out)?;
+        for item in items {
+            print_item(out, item, config)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_item(
+    out: &mut dyn Write,
+    item: &ChangeItem,
+    config: &ChangeLogConfig,
+) -> std::io::Result<()> {
+    write!(out, "* ")?;
+    let refs: Vec<String> = item
+        .refs
+        .iter()
+        .map(|reference| match config.ref_link(reference) {
+            None => escape(&reference.to_string()),
+            Some(link) => format!("`{} <{}>`__", reference, link),
+        })
+        .collect();
+    if !refs.is_empty() {
+        write!(out, "{}: ", refs.join(", "))?;
+    }
+    if !item.component.is_empty() {
+        write!(out, "``{}`` ", item.component)?;
+    }
+    write!(out, "{}", escape(&item.text))?;
+    let authors: Vec<String> = item
+        .authors
+        .iter()
+        .filter(|a| !a.is_empty())
+        .map(|a| escape(a))
+        .collect();
+    if !authors.is_empty() {
+        write!(out, " *{}*", authors.join(", "))?;
+    }
+    if let (Some(commit), Some(short)) = (&item.commit, item.short_commit()) {
+        match config.commit_link(commit) {
+            None => write!(out, " (``{}``)", short)?,
+            Some(link) => write!(out, " (`{} <{}>`__)", short, link)?,
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Escapes characters that start inline markup.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '`' | '_' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChangeLog;
+
+    const CHANGELOG: &str = r#"# Changelog
+Notable changes.
+
+<!-- CHANGELOG-CONFIG
+[keys]
+pr_link = "https://github.com/o/r/pull/{number}"
+-->
+
+## Unreleased
+
+- PR#12, #3: [core] Fix crash in *parse_line* / pk
+
+## 1.0.1 - 2020-09-15 [YANKED]
+
+> Hotfix release.
+
+- Fix NPE / pk
+"#;
+
+    #[test]
+    fn rst() {
+        let changelog = ChangeLog::import_markdown(CHANGELOG).unwrap();
+        let mut out = Vec::new();
+        changelog.print_rst(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"=========
+Changelog
+=========
+
+Notable changes.
+
+.. _unreleased:
+
+Unreleased
+----------
+
+Other
+~~~~~
+
+* `PR#12 <https://github.com/o/r/pull/12>`__, #3: ``core`` Fix crash in \*parse\_line\* *pk*
+
+.. _v1.0.1:
+
+1.0.1 - 2020-09-15 (YANKED)
+---------------------------
+
+   Hotfix release.
+
+Other
+~~~~~
+
+* Fix NPE *pk*
+"#
+        );
+    }
+
+    #[test]
+    fn escaped_title() {
+        let changelog = ChangeLog::import_markdown("# my_lib changes\n").unwrap();
+        let mut out = Vec::new();
+        changelog.print_rst(&mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .starts_with("===============\nmy\\_lib changes\n===============\n"));
+    }
+}
@@ -0,0 +1,342 @@
+use std::str::FromStr;
+
+use git2::Repository;
+use regex::Regex;
+
+use crate::error::ChgError;
+
+/// Code hosting services with known link layouts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Provider {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    Gitea,
+    Azure,
+}
+
+impl FromStr for Provider {
+    type Err = ChgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "github" => Ok(Provider::GitHub),
+            "gitlab" => Ok(Provider::GitLab),
+            "bitbucket" => Ok(Provider::Bitbucket),
+            "gitea" | "forgejo" => Ok(Provider::Gitea),
+            "azure" | "azure-devops" => Ok(Provider::Azure),
+            _ => Err(ChgError::UnknownHosting(s.to_string())),
+        }
+    }
+}
+
+impl Provider {
+    /// Guesses the provider from well-known host names.
+    fn from_host(host: &str) -> Option<Self> {
+        if host.contains("github") {
+            Some(Provider::GitHub)
+        } else if host.contains("gitlab") {
+            Some(Provider::GitLab)
+        } else if host.contains("bitbucket") {
+            Some(Provider::Bitbucket)
+        } else if host.contains("gitea") || host.contains("forgejo") || host == "codeberg.org" {
+            Some(Provider::Gitea)
+        } else if host.ends_with("dev.azure.com") || host.ends_with("visualstudio.com") {
+            Some(Provider::Azure)
+        } else {
+            None
+        }
+    }
+}
+
+/// Repository on a hosting service, derived from a remote URL.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HostedRepo {
+    pub(crate) provider: Provider,
+    /// web page of the repository, like `https://github.com/OWNER/REPO`
+    pub(crate) web_url: String,
+    /// scheme, host and port, like `https://github.com`
+    origin: String,
+}
+
+impl HostedRepo {
+    /// Recognizes remote URLs like `https://github.com/o/r.git`, `git@gitlab.com:g/r.git` or `ssh://git@host/o/r`.
+    /// Hosts with unknown names need the `provider`, like self-hosted GitLab on `git.example.com`.
+    pub(crate) fn from_remote_url(url: &str, provider: Option<Provider>) -> Option<Self> {
+        let regex = Regex::new(
+            r"^(?:(?P<scheme>https?|ssh|git)://(?:[^@/]+@)?(?P<host>[^/:]+)(?::(?P<port>\d+))?/|(?:[^@/]+@)?(?P<scp_host>[^/:]+):)(?P<path>.+?)(?:\.git)?/?$",
+        )
+        .unwrap();
+        let captures = regex.captures(url.trim())?;
+        let host = captures
+            .name("host")
+            .or_else(|| captures.name("scp_host"))?
+            .as_str();
+        let path = captures["path"].trim_start_matches('/');
+        let provider = provider.or_else(|| Provider::from_host(host))?;
+        // ports of ssh remotes are not for the web
+        let (scheme, port) = match captures.name("scheme").map(|m| m.as_str()) {
+            Some("http") => ("http", captures.name("port")),
+            Some("https") => ("https", captures.name("port")),
+            _ => ("https", None),
+        };
+        let origin = match port {
+            None => format!("{}://{}", scheme, host),
+            Some(port) => format!("{}://{}:{}", scheme, host, port.as_str()),
+        };
+        let web_url = if provider == Provider::Azure {
+            azure_web_url(&origin, host, path)?
+        } else {
+            format!("{}/{}", origin, path)
+        };
+        Some(Self {
+            provider,
+            web_url,
+            origin,
+        })
+    }
+
+    /// Hosted repository of the first recognized remote URL.
+    /// `hosting` names the provider for unknown hosts, if not empty.
+    pub(crate) fn from_remote_urls(
+        urls: &[String],
+        hosting: &str,
+    ) -> Result<Option<Self>, ChgError> {
+        let provider = if hosting.is_empty() {
+            None
+        } else {
+            Some(Provider::from_str(hosting)?)
+        };
+        Ok(urls
+            .iter()
+            .find_map(|url| Self::from_remote_url(url, provider)))
+    }
+
+    pub(crate) fn from_repository(
+        repo: &Repository,
+        hosting: &str,
+    ) -> Result<Option<Self>, ChgError> {
+        Self::from_remote_urls(&remote_urls(repo)?, hosting)
+    }
+
+    /// Link to issue, with `{number}` placeholder.
+    pub(crate) fn issue_link(&self) -> String {
+        match self.provider {
+            Provider::GitHub | Provider::Bitbucket | Provider::Gitea => {
+                format!("{}/issues/{{number}}", self.web_url)
+            }
+            Provider::GitLab => format!("{}/-/issues/{{number}}", self.web_url),
+            // work items belong to the project, not to the repository
+            Provider::Azure => format!(
+                "{}/_workitems/edit/{{number}}",
+                self.web_url.split("/_git/").next().unwrap_or_default()
+            ),
+        }
+    }
+
+    /// Link to pull or merge request, with `{number}` placeholder.
+    pub(crate) fn pr_link(&self) -> String {
+        match self.provider {
+            Provider::GitHub => format!("{}/pull/{{number}}", self.web_url),
+            Provider::GitLab => format!("{}/-/merge_requests/{{number}}", self.web_url),
+            Provider::Bitbucket => format!("{}/pull-requests/{{number}}", self.web_url),
+            Provider::Gitea => format!("{}/pulls/{{number}}", self.web_url),
+            Provider::Azure => format!("{}/pullrequest/{{number}}", self.web_url),
+        }
+    }
+
+    /// Link to issue of another project on the same host, with `{project}` and `{number}` placeholders.
+    pub(crate) fn project_issue_link(&self) -> Option<String> {
+        match self.provider {
+            Provider::GitHub | Provider::Bitbucket | Provider::Gitea => {
+                Some(format!("{}/{{project}}/issues/{{number}}", self.origin))
+            }
+            Provider::GitLab => Some(format!("{}/{{project}}/-/issues/{{number}}", self.origin)),
+            Provider::Azure => None,
+        }
+    }
+
+    /// Link to pull or merge request of another project on the same host.
+    pub(crate) fn project_pr_link(&self) -> Option<String> {
+        match self.provider {
+            Provider::GitHub => Some(format!("{}/{{project}}/pull/{{number}}", self.origin)),
+            Provider::GitLab => Some(format!(
+                "{}/{{project}}/-/merge_requests/{{number}}",
+                self.origin
+            )),
+            Provider::Bitbucket | Provider::Gitea | Provider::Azure => None,
+        }
+    }
+
+    /// Link templates per `[keys]` config name.
+    pub(crate) fn links(&self) -> Vec<(&'static str, String)> {
+        let mut links = vec![
+            ("issue_link", self.issue_link()),
+            ("pr_link", self.pr_link()),
+            ("commit_link", self.commit_link()),
+            ("compare_link", self.compare_link()),
+        ];
+        if let Some(link) = self.project_issue_link() {
+            links.push(("project_issue_link", link));
+        }
+        if let Some(link) = self.project_pr_link() {
+            links.push(("project_pr_link", link));
+        }
+        links
+    }
+
+    /// Link to commit, with `{commit}` placeholder.
+    pub(crate) fn commit_link(&self) -> String {
+        match self.provider {
+            Provider::GitHub | Provider::Gitea | Provider::Azure => {
+                format!("{}/commit/{{commit}}", self.web_url)
+            }
+            Provider::GitLab => format!("{}/-/commit/{{commit}}", self.web_url),
+            Provider::Bitbucket => format!("{}/commits/{{commit}}", self.web_url),
+        }
+    }
+
+    /// Link to comparison of two revisions, with `{from}` and `{to}` placeholders.
+    pub(crate) fn compare_link(&self) -> String {
+        format!("{}/{}", self.web_url, self.compare_path("{from}", "{to}"))
+    }
+
+    /// Path of the comparison of two revisions, relative to [`web_url`](Self::web_url).
+    pub(crate) fn compare_path(&self, from: &str, to: &str) -> String {
+        match self.provider {
+            Provider::GitHub | Provider::Gitea => format!("compare/{}...{}", from, to),
+            Provider::GitLab => format!("-/compare/{}...{}", from, to),
+            Provider::Bitbucket => format!("branches/compare/{}%0D{}", to, from),
+            Provider::Azure => format!(
+                "branchCompare?baseVersion=GT{}&targetVersion=GT{}",
+                from, to
+            ),
+        }
+    }
+}
+
+/// Web URL of Azure DevOps repository, like `https://dev.azure.com/ORG/PROJECT/_git/REPO`.
+fn azure_web_url(origin: &str, host: &str, path: &str) -> Option<String> {
+    let parts: Vec<&str> = path.split('/').collect();
+    match parts.as_slice() {
+        // ssh: `git@ssh.dev.azure.com:v3/ORG/PROJECT/REPO` or `ORG@vs-ssh.visualstudio.com:v3/ORG/PROJECT/REPO`
+        ["v3", org, project, repo] => Some(format!(
+            "https://dev.azure.com/{}/{}/_git/{}",
+            org, project, repo
+        )),
+        // https: `https://dev.azure.com/ORG/PROJECT/_git/REPO` or `https://ORG.visualstudio.com/PROJECT/_git/REPO`
+        _ if path.contains("/_git/") && !host.starts_with("ssh.") => {
+            Some(format!("{}/{}", origin, path))
+        }
+        _ => None,
+    }
+}
+
+/// URLs of the repository remotes, `origin` first.
+pub(crate) fn remote_urls(repo: &Repository) -> Result<Vec<String>, ChgError> {
+    let mut names = repo
+        .remotes()?
+        .iter()
+        .flatten()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    names.sort_by_key(|name| name != "origin");
+    Ok(names
+        .iter()
+        .filter_map(|name| repo.find_remote(name).ok())
+        .filter_map(|remote| remote.url().map(str::to_string))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hosting::{HostedRepo, Provider};
+
+    fn web_url(url: &str) -> String {
+        HostedRepo::from_remote_url(url, None).unwrap().web_url
+    }
+
+    #[test]
+    fn remote_urls() {
+        let github = HostedRepo::from_remote_url("git@github.com:o/r.git", None).unwrap();
+        assert_eq!(github.provider, Provider::GitHub);
+        assert_eq!(github.web_url, "https://github.com/o/r");
+        assert_eq!(github.pr_link(), "https://github.com/o/r/pull/{number}");
+        assert_eq!(
+            HostedRepo::from_remote_url("https://github.com/o/r", None).unwrap(),
+            github
+        );
+
+        let gitlab = HostedRepo::from_remote_url("ssh://git@gitlab.com/g/r.git", None).unwrap();
+        assert_eq!(gitlab.provider, Provider::GitLab);
+        assert_eq!(
+            gitlab.issue_link(),
+            "https://gitlab.com/g/r/-/issues/{number}"
+        );
+        assert_eq!(gitlab.compare_path("v1", "v2"), "-/compare/v1...v2");
+        assert_eq!(
+            gitlab.project_pr_link().unwrap(),
+            "https://gitlab.com/{project}/-/merge_requests/{number}"
+        );
+
+        assert!(HostedRepo::from_remote_url("/srv/git/r.git", None).is_none());
+    }
+
+    #[test]
+    fn self_hosted_and_other_providers() {
+        assert_eq!(
+            web_url("ssh://git@gitlab.example.com:2222/group/sub/repo.git"),
+            "https://gitlab.example.com/group/sub/repo"
+        );
+        assert_eq!(
+            web_url("http://gitlab.example.com:8080/group/sub/repo.git"),
+            "http://gitlab.example.com:8080/group/sub/repo"
+        );
+        assert!(HostedRepo::from_remote_url("git@git.example.com:g/r.git", None).is_none());
+        let gitlab =
+            HostedRepo::from_remote_url("git@git.example.com:g/s/r.git", Some(Provider::GitLab))
+                .unwrap();
+        assert_eq!(
+            gitlab.pr_link(),
+            "https://git.example.com/g/s/r/-/merge_requests/{number}"
+        );
+
+        let bitbucket = HostedRepo::from_remote_url("git@bitbucket.org:o/r.git", None).unwrap();
+        assert_eq!(
+            bitbucket.pr_link(),
+            "https://bitbucket.org/o/r/pull-requests/{number}"
+        );
+        assert_eq!(
+            bitbucket.compare_link(),
+            "https://bitbucket.org/o/r/branches/compare/{to}%0D{from}"
+        );
+
+        let gitea = HostedRepo::from_remote_url("https://codeberg.org/o/r.git", None).unwrap();
+        assert_eq!(gitea.provider, Provider::Gitea);
+        assert_eq!(gitea.pr_link(), "https://codeberg.org/o/r/pulls/{number}");
+
+        let azure = "https://dev.azure.com/org/proj/_git/repo";
+        assert_eq!(
+            web_url("https://org@dev.azure.com/org/proj/_git/repo"),
+            azure
+        );
+        assert_eq!(web_url("git@ssh.dev.azure.com:v3/org/proj/repo"), azure);
+        assert_eq!(
+            web_url("org@vs-ssh.visualstudio.com:v3/org/proj/repo"),
+            azure
+        );
+        assert_eq!(
+            web_url("https://org.visualstudio.com/proj/_git/repo"),
+            "https://org.visualstudio.com/proj/_git/repo"
+        );
+        let azure = HostedRepo::from_remote_url(azure, None).unwrap();
+        assert_eq!(
+            azure.issue_link(),
+            "https://dev.azure.com/org/proj/_workitems/edit/{number}"
+        );
+        assert_eq!(
+            azure.pr_link(),
+            "https://dev.azure.com/org/proj/_git/repo/pullrequest/{number}"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::api::ChangeType;
+use crate::error::ChgError;
+use crate::ChangeLogConfig;
+
+/// How much we trust the guessed change type.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Copy, Clone)]
+pub enum Confidence {
+    /// nothing matched, or only a weak hint
+    Low,
+    /// leading verb of the subject
+    Medium,
+    /// explicit marker: PR label or bracket tag
+    High,
+}
+
+/// Result of classifying one commit.
+#[derive(Debug, Copy, Clone)]
+pub struct Classification {
+    pub change_type: ChangeType,
+    pub confidence: Confidence,
+}
+
+/// Guesses [`ChangeType`] for projects that do not use any structured commit message convention.
+pub struct ChangeTypeClassifier {
+    /// lowercase keywords, checked in order
+    keywords: Vec<(String, ChangeType)>,
+    /// lowercase PR labels
+    labels: HashMap<String, ChangeType>,
+}
+
+fn default_keywords(change_type: ChangeType) -> &'static [&'static str] {
+    match change_type {
+        ChangeType::Added => &["add", "implement", "introduce", "support", "feature", "feat", "new"],
+        ChangeType::Fixed => &["fix", "bugfix", "hotfix", "resolve", "correct", "bug"],
+        ChangeType::Changed => &["change", "update", "improve", "upgrade", "bump", "enhance", "make", "use"],
+        ChangeType::Deprecated => &["deprecate"],
+        ChangeType::Removed => &["remove", "delete", "drop"],
+        ChangeType::Refactored => &["refactor", "cleanup", "clean", "rename", "simplify", "restructure"],
+        ChangeType::Other => &[],
+    }
+}
+
+impl ChangeTypeClassifier {
+    pub fn from_config(config: &ChangeLogConfig) -> Result<Self, ChgError> {
+        let mut custom: HashMap<ChangeType, Vec<String>> = HashMap::new();
+        for (change_type, words) in &config.types.keywords {
+            custom.insert(ChangeType::from_str(change_type)?, words.clone());
+        }
+        let mut keywords = Vec::new();
+        for change_type in ChangeType::ALL.iter() {
+            match custom.get(change_type) {
+                Some(words) => {
+                    for word in words {
+                        keywords.push((word.to_lowercase(), *change_type));
+                    }
+                }
+                None => {
+                    for word in default_keywords(*change_type) {
+                        keywords.push((word.to_string(), *change_type));
+                    }
+                }
+            }
+        }
+        let mut labels = HashMap::new();
+        for (label, change_type) in &config.types.labels {
+            labels.insert(label.to_lowercase(), ChangeType::from_str(change_type)?);
+        }
+        Ok(Self { keywords, labels })
+    }
+
+    /// Classifies commit by PR labels (if any), then by bracket tags and leading verb of the subject.
+    pub fn classify(&self, subject: &str, labels: &[String]) -> Classification {
+        for label in labels {
+            if let Some(change_type) = self.labels.get(&label.to_lowercase()) {
+                return Classification {
+                    change_type: *change_type,
+                    confidence: Confidence::High,
+                };
+            }
+        }
+
+        // bracket tags like `[BUGFIX]`; unknown ones (typically components) are skipped
+        let mut rest = subject.trim();
+        while rest.starts_with('[') {
+            let end = match rest.find(']') {
+                None => break,
+                Some(end) => end,
+            };
+            let tag = rest[1..end].trim().to_lowercase();
+            if let Some(change_type) = self.keyword_type(&tag, false) {
+                return Classification {
+                    change_type,
+                    confidence: Confidence::High,
+                };
+            }
+            rest = rest[end + 1..].trim_start();
+        }
+
+        // leading verb
+        let first_word: String = rest
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '-')
+            .collect::<String>()
+            .to_lowercase();
+        if let Some(change_type) = self.keyword_type(&first_word, true) {
+            return Classification {
+                change_type,
+                confidence: Confidence::Medium,
+            };
+        }
+
+        Classification {
+            change_type: ChangeType::Other,
+            confidence: Confidence::Low,
+        }
+    }
+
+    /// True if the bracket tag (like `BUGFIX` in `[BUGFIX]`) denotes a change type rather than a component.
+    pub fn is_type_tag(&self, tag: &str) -> bool {
+        self.keyword_type(&tag.trim().to_lowercase(), false).is_some()
+    }
+
+    /// Finds keyword matching the word; with `inflected`, also accepts forms like "Fixes", "Added", "Removing".
+    fn keyword_type(&self, word: &str, inflected: bool) -> Option<ChangeType> {
+        if word.is_empty() {
+            return None;
+        }
+        for (keyword, change_type) in &self.keywords {
+            if word == keyword {
+                return Some(*change_type);
+            }
+            if inflected {
+                if let Some(suffix) = word.strip_prefix(keyword.as_str()) {
+                    if ["s", "es", "d", "ed", "ing"].contains(&suffix) {
+                        return Some(*change_type);
+                    }
+                }
+                // "remove" -> "removing"
+                if let Some(stem) = keyword.strip_suffix('e') {
+                    if word == format!("{}ing", stem) {
+                        return Some(*change_type);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::ChangeType;
+    use crate::imports::change_type::{ChangeTypeClassifier, Confidence};
+    use crate::ChangeLogConfig;
+
+    #[test]
+    fn leading_verb() {
+        let classifier = ChangeTypeClassifier::from_config(&ChangeLogConfig::default()).unwrap();
+        let c = classifier.classify("Fixed NPE when parsing empty mojo", &[]);
+        assert_eq!(c.change_type, ChangeType::Fixed);
+        assert_eq!(c.confidence, Confidence::Medium);
+        let c = classifier.classify("[py] Removing obsolete wrapper", &[]);
+        assert_eq!(c.change_type, ChangeType::Removed);
+        let c = classifier.classify("Addressing review comments", &[]);
+        assert_eq!(c.change_type, ChangeType::Other);
+        assert_eq!(c.confidence, Confidence::Low);
+    }
+
+    #[test]
+    fn bracket_tag() {
+        let classifier = ChangeTypeClassifier::from_config(&ChangeLogConfig::default()).unwrap();
+        let c = classifier.classify("[cpp][BUGFIX] tree shap computing", &[]);
+        assert_eq!(c.change_type, ChangeType::Fixed);
+        assert_eq!(c.confidence, Confidence::High);
+    }
+
+    #[test]
+    fn configured_labels_and_keywords() {
+        let mut config = ChangeLogConfig::default();
+        config
+            .types
+            .keywords
+            .insert("deprecated".to_string(), vec!["Sunset".to_string()]);
+        config
+            .types
+            .labels
+            .insert("enhancement".to_string(), "added".to_string());
+        let classifier = ChangeTypeClassifier::from_config(&config).unwrap();
+        let c = classifier.classify("Sunset old API", &[]);
+        assert_eq!(c.change_type, ChangeType::Deprecated);
+        let c = classifier.classify("Fix typo", &["Enhancement".to_string()]);
+        assert_eq!(c.change_type, ChangeType::Added);
+        assert_eq!(c.confidence, Confidence::High);
+    }
+}
@@ -2,6 +2,14 @@ use std::str::Lines;
 
 use regex::Regex;
 
+use std::str::FromStr;
+
+use crate::api::{ChangeType, Reference};
+use crate::error::ChgError;
+use crate::imports::release_profile::ReleasePatterns;
+use crate::imports::trailers::{normalize_key, Message, TrailerField, DEFAULT_TRAILER_FIELDS};
+use crate::ChangeLogConfig;
+
 pub enum CommitMessage {
     /// Regular commit that contributes to code and is equipped with some fields
     /// Format is heavily project-dependent, typically manually composed.
@@ -9,12 +17,20 @@ pub enum CommitMessage {
         /// best component name candidate
         component: String,
         /// references to commits, issues, PRs
-        refs: Vec<String>,
+        refs: Vec<Reference>,
         /// first line in message
         subject: String,
-        /// remaining lines
+        /// body without trailers and closing footers
         details: String,
+        /// text from the `Changelog:` trailer, preferred over subject and PR title
+        changelog_text: Option<String>,
+        /// change type from a trailer, like `Changelog-Type: fixed`
+        change_type: Option<ChangeType>,
+        /// authors from trailers, like `Co-authored-by:`
+        co_authors: Vec<String>,
     },
+    /// Contribution left out of the changelog by a trailer, like `Changelog: skip`
+    Skipped { subject: String },
     /// The commit recording release action; usually switches version to a next release number
     /// Format is heavily project-dependent, sometimes even manually composed.
     Release {
@@ -33,16 +49,37 @@ pub enum CommitMessage {
     Revert { orig_msg: String },
 }
 
+impl CommitMessage {
+    /// True for contributions referring to PRs, issues etc.
+    pub fn has_refs(&self) -> bool {
+        match self {
+            CommitMessage::Contribution { refs, .. } => !refs.is_empty(),
+            _ => false,
+        }
+    }
+}
+
 pub struct CommitMessageAnalyzer {
-    /// valid components
-    // components: Vec<String>,
     // revision_prefix: String,
     // issue_prefix: String,
     pr_mergecommit_regex: Regex,
+    mr_mergecommit_regex: Regex,
+    mr_trailer_regex: Regex,
     pr_squash_regex: Regex,
     pr_kk_closes: Regex,
-    release_regex: Regex,
-    postrelease_regex: Regex,
+    closing_regex: Regex,
+    issue_ref_regex: Regex,
+    release_patterns: ReleasePatterns,
+    trackers: Vec<TrackerKeys>,
+    /// normalized trailer keys with the item field they map to
+    trailer_fields: Vec<(String, TrailerField)>,
+}
+
+/// Key patterns of an issue tracker from `[keys.trackers]`.
+struct TrackerKeys {
+    key_regex: Regex,
+    /// keys at the start and at the end of subject, with separators
+    strip_regexes: Option<(Regex, Regex)>,
 }
 
 const GIT_REVERT_PREFIX: &str = "Revert \"";
@@ -53,16 +90,151 @@ impl CommitMessageAnalyzer {
             pr_mergecommit_regex: Regex::new(
                 r"Merge pull request #(?P<pr>\d+) from (?P<branch>.*)",
             )?,
-            pr_squash_regex: Regex::new(r"^(?P<subject>.*) \(#(?P<pr>\d+)\)$")?,
+            mr_mergecommit_regex: Regex::new(r"^Merge branch '[^']*' into '[^']*'$")?,
+            mr_trailer_regex: Regex::new(r"^See merge request (?:\S+)?!(?P<mr>\d+)$")?,
+            pr_squash_regex: Regex::new(r"^(?P<subject>.*) \((?P<kind>[#!])(?P<pr>\d+)\)$")?,
             pr_kk_closes: Regex::new(r"(?P<drop>\.?\s+(?i:CLOSES?)\s*#\s*(?P<issue>\d+))")?,
-            release_regex: Regex::new(
-                r"(?i:RELEASE[SD]?)\s+[\D]*(?P<version>\d+[\\.\-][\\.\-\d]+)",
+            closing_regex: Regex::new(
+                r"(?i:\b(?:close[sd]?|fix(?:e[sd])?|resolve[sd]?|implement(?:s|ed)?))\s*:?\s+(?P<refs>(?:[\w.\-/]*#\d+(?:,\s*|\s+and\s+|\s+)?)+)",
             )?,
-            postrelease_regex: Regex::new(r"(?i:NEXT DEVEL CYCLE)\s*[\D]*(?P<version>.*)")?,
+            issue_ref_regex: Regex::new(r"(?P<project>[\w.\-]+(?:/[\w.\-]+)+)?#(?P<issue>\d+)")?,
+            release_patterns: ReleasePatterns::default(),
+            trackers: vec![],
+            trailer_fields: DEFAULT_TRAILER_FIELDS
+                .iter()
+                .map(|(key, field)| (normalize_key(key), *field))
+                .collect(),
         })
     }
 
+    /// Analyzer that also extracts keys of `[keys.trackers]`.
+    pub fn from_config(config: &ChangeLogConfig) -> Result<Self, ChgError> {
+        let mut analyzer = Self::init().unwrap();
+        analyzer.release_patterns = ReleasePatterns::from_config(config)?;
+        for (name, tracker) in &config.keys.trackers {
+            let invalid =
+                |e: regex::Error| ChgError::InvalidTrackerKey(name.clone(), e.to_string());
+            let key = &tracker.key;
+            let strip_regexes = if tracker.strip {
+                Some((
+                    Regex::new(&format!(r"^(?:[\[(]?(?:{})[\])]?[:,]?\s*)+", key))
+                        .map_err(invalid)?,
+                    Regex::new(&format!(r"(?:\s*[\[(]?(?:{})[\])]?)+$", key)).map_err(invalid)?,
+                ))
+            } else {
+                None
+            };
+            analyzer.trackers.push(TrackerKeys {
+                key_regex: Regex::new(&format!(r"\b(?:{})\b", key)).map_err(invalid)?,
+                strip_regexes,
+            });
+        }
+        for (key, field) in &config.trailers {
+            let key = normalize_key(key);
+            let field = TrailerField::from_str(field)?;
+            analyzer.trailer_fields.retain(|(k, _)| *k != key);
+            analyzer.trailer_fields.push((key, field));
+        }
+        Ok(analyzer)
+    }
+
     pub fn analyze(&self, msg: &str) -> CommitMessage {
+        let message = Message::parse(msg);
+        let mut cm = self.analyze_structure(msg, &message);
+        if let CommitMessage::Contribution { refs, subject, .. } = &mut cm {
+            for tracker in &self.trackers {
+                for key in tracker.key_regex.find_iter(msg) {
+                    let key = Reference::ExternalTracker(key.as_str().to_string());
+                    if !refs.contains(&key) {
+                        refs.push(key);
+                    }
+                }
+                if let Some((leading, trailing)) = &tracker.strip_regexes {
+                    let stripped = leading.replace(subject, "");
+                    let stripped = trailing.replace(&stripped, "").trim().to_string();
+                    // keep keys that are all there is
+                    if !stripped.is_empty() {
+                        *subject = stripped;
+                    }
+                }
+            }
+        }
+        if self.apply_trailers(&message, &mut cm) {
+            if let CommitMessage::Contribution { subject, .. } = cm {
+                return CommitMessage::Skipped { subject };
+            }
+        }
+        cm
+    }
+
+    /// Moves trailer values to fields of the contribution, per `[trailers]` config; true if it should be skipped.
+    fn apply_trailers(&self, message: &Message, cm: &mut CommitMessage) -> bool {
+        let (component, refs, details, changelog_text, change_type, co_authors) = match cm {
+            CommitMessage::Contribution {
+                component,
+                refs,
+                details,
+                changelog_text,
+                change_type,
+                co_authors,
+                ..
+            } => (
+                component,
+                refs,
+                details,
+                changelog_text,
+                change_type,
+                co_authors,
+            ),
+            _ => return false,
+        };
+        let mut skip = false;
+        for trailer in &message.trailers {
+            let key = normalize_key(&trailer.key);
+            let field = match self.trailer_fields.iter().find(|(k, _)| *k == key) {
+                None => continue,
+                Some((_, field)) => *field,
+            };
+            let value = trailer.value.as_str();
+            match field {
+                TrailerField::Text if value.eq_ignore_ascii_case("skip") => skip = true,
+                TrailerField::Text => *changelog_text = Some(value.to_string()),
+                TrailerField::Details => {
+                    if !details.is_empty() {
+                        details.push('\n');
+                    }
+                    details.push_str(value);
+                }
+                TrailerField::Type => match ChangeType::from_str(value) {
+                    Ok(t) => *change_type = Some(t),
+                    Err(e) => warn!("Ignoring trailer '{}': {}", trailer.key, e),
+                },
+                TrailerField::Component => *component = value.to_string(),
+                TrailerField::Refs => {
+                    for r in value.split(',').filter(|r| !r.trim().is_empty()) {
+                        let r = Reference::parse(r);
+                        if !refs.contains(&r) {
+                            refs.push(r);
+                        }
+                    }
+                }
+                TrailerField::Authors => {
+                    // `Name <email>`
+                    let name = value.split('<').next().unwrap_or("").trim().to_string();
+                    if !name.is_empty() && !co_authors.contains(&name) {
+                        co_authors.push(name);
+                    }
+                }
+                TrailerField::Skip => {
+                    skip |= !matches!(value.to_ascii_lowercase().as_str(), "false" | "no")
+                }
+                TrailerField::None => {}
+            }
+        }
+        skip
+    }
+
+    fn analyze_structure(&self, msg: &str, message: &Message) -> CommitMessage {
         let msg = msg.trim();
         // Empty message
         if msg.is_empty() {
@@ -71,6 +243,9 @@ impl CommitMessageAnalyzer {
                 refs: vec![],
                 subject: "".to_string(),
                 details: "".to_string(),
+                changelog_text: None,
+                change_type: None,
+                co_authors: vec![],
             };
         }
         // Reverting a commit
@@ -84,24 +259,30 @@ impl CommitMessageAnalyzer {
         let first_line = lines.next().unwrap_or("");
 
         // Release commit
-        if let Some(captures) = self.release_regex.captures(first_line) {
-            if let Some(m) = captures.name("version") {
-                let version = m.as_str().to_string();
-                return CommitMessage::Release { version };
+        for regex in &self.release_patterns.release {
+            if let Some(captures) = regex.captures(first_line) {
+                if let Some(m) = captures.name("version") {
+                    let version = m.as_str().to_string();
+                    return CommitMessage::Release { version };
+                }
             }
         }
 
         // Post-release commit
-        if let Some(captures) = self.postrelease_regex.captures(first_line) {
-            if let Some(m) = captures.name("version") {
-                let ref_ver = m.as_str().to_string();
-                return CommitMessage::PostRelease { ref_ver };
+        for regex in &self.release_patterns.postrelease {
+            if let Some(captures) = regex.captures(first_line) {
+                let ref_ver = captures.name("version").map_or("", |m| m.as_str());
+                return CommitMessage::PostRelease {
+                    ref_ver: ref_ver.to_string(),
+                };
             }
         }
 
-        // Github: merged pull-request
+        // merged pull-request or merge request
+        let body: Vec<&str> = lines.clone().collect();
         if let Some((pr, subject)) = self.detect_pr_merge(first_line, &mut lines) {
-            let mut refs = vec![format!("PR#{}", pr)];
+            let details = self.details(message, &subject);
+            let mut refs = vec![pr];
             // here we can apply any additional regexes to receive more issues or other info from the message text
             // for now, use thirdwing's (=KK) habit to add ' closes #1234' to the PR title
             let subject = match self.pr_kk_closes.captures(&subject) {
@@ -111,7 +292,7 @@ impl CommitMessageAnalyzer {
                             None => (m.start(), m.end()),
                             Some(m) => (m.start(), m.end()),
                         };
-                        refs.push(format!("#{}", m.as_str()));
+                        refs.push(Reference::parse(&format!("#{}", m.as_str())));
                         format!(
                             "{}{}",
                             &subject.as_str()[0..drop_start],
@@ -122,27 +303,83 @@ impl CommitMessageAnalyzer {
                 },
                 None => subject,
             };
+            self.closing_refs(&body, &mut refs);
             return CommitMessage::Contribution {
                 component: "".to_string(),
                 refs,
                 subject,
-                details: "".to_string(),
+                details,
+                changelog_text: None,
+                change_type: None,
+                co_authors: vec![],
             };
         }
         // otherwise
+        let mut refs = vec![];
+        self.closing_refs(&body, &mut refs);
         CommitMessage::Contribution {
-            component: "N/A".to_string(),
-            refs: vec![],
+            component: "".to_string(),
+            refs,
             subject: first_line.to_string(),
-            details: "".to_string(),
+            details: self.details(message, first_line),
+            changelog_text: None,
+            change_type: None,
+            co_authors: vec![],
+        }
+    }
+
+    /// Message body without the subject repeated there, and without closing footers.
+    fn details(&self, message: &Message, subject: &str) -> String {
+        let mut lines = message
+            .body
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !self.mr_trailer_regex.is_match(line))
+            .filter(|line| !matches!(self.closing_regex.find(line), Some(m) if m.start() == 0))
+            .peekable();
+        if lines.peek() == Some(&subject.trim()) {
+            lines.next();
+        }
+        lines.collect::<Vec<&str>>().join("\n")
+    }
+
+    /// Adds issues closed by body lines like `Closes #12, #13` or `Fixes group/project#45`.
+    fn closing_refs(&self, body: &[&str], refs: &mut Vec<Reference>) {
+        for line in body {
+            for captures in self.closing_regex.captures_iter(line) {
+                for issue in self.issue_ref_regex.captures_iter(&captures["refs"]) {
+                    let reference = Reference::parse(&issue[0]);
+                    if !refs.contains(&reference) {
+                        refs.push(reference);
+                    }
+                }
+            }
         }
     }
 
-    /// Find PR number and "clean" message subject.
-    fn detect_pr_merge(&self, first_line: &str, lines: &mut Lines) -> Option<(String, String)> {
+    /// Find PR (`PR#123`) or GitLab merge request (`!123`) ref, and "clean" message subject.
+    fn detect_pr_merge(&self, first_line: &str, lines: &mut Lines) -> Option<(Reference, String)> {
+        if self.mr_mergecommit_regex.is_match(first_line) {
+            let body: Vec<&str> = lines.map(str::trim).filter(|l| !l.is_empty()).collect();
+            let mr = body
+                .iter()
+                .find_map(|line| self.mr_trailer_regex.captures(line))?;
+            let mr = Reference::parse(&format!("!{}", &mr["mr"]));
+            // MR title, unless the description is empty
+            let subject = match body.first() {
+                Some(line)
+                    if !self.mr_trailer_regex.is_match(line)
+                        && !self.closing_regex.is_match(line) =>
+                {
+                    line.to_string()
+                }
+                _ => first_line.to_string(),
+            };
+            return Some((mr, subject));
+        }
         if let Some(captures) = self.pr_mergecommit_regex.captures(first_line) {
             if let Some(m) = captures.name("pr") {
-                let pr = m.as_str().to_string();
+                let pr = Reference::parse(&format!("PR#{}", m.as_str()));
                 // use first non-empty line
                 for line in lines {
                     if !line.trim().is_empty() {
@@ -155,7 +392,10 @@ impl CommitMessageAnalyzer {
         }
         if let Some(captures) = self.pr_squash_regex.captures(first_line) {
             if let Some(m) = captures.name("pr") {
-                let pr = m.as_str().to_string();
+                let pr = match &captures["kind"] {
+                    "!" => Reference::parse(&format!("!{}", m.as_str())),
+                    _ => Reference::parse(&format!("PR#{}", m.as_str())),
+                };
                 let subject = match captures.name("subject") {
                     Some(m) => m.as_str(),
                     None => first_line,
@@ -169,7 +409,9 @@ impl CommitMessageAnalyzer {
 
 #[cfg(test)]
 mod tests {
+    use crate::api::{ChangeType, Reference};
     use crate::imports::commit_msg::{CommitMessage, CommitMessageAnalyzer};
+    use crate::ChangeLogConfig;
 
     #[test]
     fn revert_commit() {
@@ -195,11 +437,11 @@ mod tests {
                 component,
                 refs,
                 subject,
-                details: _,
+                ..
             } => {
                 println!(
                     "pr_merge_commit: {} [{}] {}",
-                    refs.join(", "),
+                    Reference::join(&refs),
                     component,
                     subject
                 );
@@ -222,11 +464,11 @@ mod tests {
                 component,
                 refs,
                 subject,
-                details: _,
+                ..
             } => {
                 println!(
                     "pr_merge_squash: {} [{}] {}",
-                    refs.join(", "),
+                    Reference::join(&refs),
                     component,
                     subject
                 );
@@ -250,11 +492,11 @@ mod tests {
                 component,
                 refs,
                 subject,
-                details: _,
+                ..
             } => {
                 println!(
                     "pr_kk_close: {} [{}] {}",
-                    refs.join(", "),
+                    Reference::join(&refs),
                     component,
                     subject
                 );
@@ -268,6 +510,111 @@ mod tests {
         }
     }
 
+    #[test]
+    fn gitlab_merge_request() {
+        let cmp = CommitMessageAnalyzer::init().unwrap();
+        let commit = cmp.analyze(
+            "Merge branch 'fix-parser' into 'main'\n\nFix parser crash\n\nCloses #12, #13 and group/sub/proj#45\n\nSee merge request group/proj!123",
+        );
+        match commit {
+            CommitMessage::Contribution {
+                refs,
+                subject,
+                details,
+                ..
+            } => {
+                assert_eq!(refs, vec!["!123", "#12", "#13", "group/sub/proj#45"]);
+                assert_eq!(subject, "Fix parser crash");
+                assert_eq!(details, "");
+            }
+            _ => panic!(""),
+        }
+
+        let commit = cmp.analyze("Merge branch 'x' into 'main'\n\nSee merge request group/proj!7");
+        match commit {
+            CommitMessage::Contribution { refs, subject, .. } => {
+                assert_eq!(refs, vec!["!7"]);
+                assert_eq!(subject, "Merge branch 'x' into 'main'");
+            }
+            _ => panic!(""),
+        }
+
+        let commit = cmp.analyze("Fix NPE (!8)\n\nFixes: #3");
+        match commit {
+            CommitMessage::Contribution { refs, subject, .. } => {
+                assert_eq!(refs, vec!["!8", "#3"]);
+                assert_eq!(subject, "Fix NPE");
+            }
+            _ => panic!(""),
+        }
+    }
+
+    #[test]
+    fn tracker_keys() {
+        let config = ChangeLogConfig::parse_embedded(
+            "<!-- CHANGELOG-CONFIG\n[keys.trackers.jira]\nkey = \"PROJ-\\\\d+\"\nstrip = true\n-->",
+        )
+        .unwrap();
+        let cmp = CommitMessageAnalyzer::from_config(&config).unwrap();
+        let commit = cmp.analyze("PROJ-1234: Fix crash (#12)\n\nAlso fixes PROJ-17 and NOPROJ-3.");
+        match commit {
+            CommitMessage::Contribution { refs, subject, .. } => {
+                assert_eq!(refs, vec!["PR#12", "PROJ-1234", "PROJ-17"]);
+                assert_eq!(subject, "Fix crash");
+            }
+            _ => panic!(""),
+        }
+        let commit = cmp.analyze("[PROJ-5] Add parser [PROJ-6]");
+        match commit {
+            CommitMessage::Contribution { refs, subject, .. } => {
+                assert_eq!(refs, vec!["PROJ-5", "PROJ-6"]);
+                assert_eq!(subject, "Add parser");
+            }
+            _ => panic!(""),
+        }
+    }
+
+    #[test]
+    fn trailer_fields() {
+        let config = ChangeLogConfig::parse_embedded(
+            "<!-- CHANGELOG-CONFIG\n[trailers]\nJira = \"refs\"\nSigned-off-by = \"authors\"\nComponent = \"none\"\n-->",
+        )
+        .unwrap();
+        let cmp = CommitMessageAnalyzer::from_config(&config).unwrap();
+        let commit = cmp.analyze(
+            "Fix parser (#12)\n\nHandles empty input.\n\nChangelog-Type: fixed\nJira: PROJ-1\nRefs: #3, #4\nComponent: core\nCo-authored-by: Qiang Kou <kou@example.com>\nSigned-off-by: Petr Kozelka <pk@example.com>",
+        );
+        match commit {
+            CommitMessage::Contribution {
+                component,
+                refs,
+                details,
+                change_type,
+                co_authors,
+                ..
+            } => {
+                assert_eq!(refs, vec!["PR#12", "PROJ-1", "#3", "#4"]);
+                assert_eq!(component, "");
+                assert_eq!(details, "Handles empty input.");
+                assert_eq!(change_type, Some(ChangeType::Fixed));
+                assert_eq!(co_authors, vec!["Qiang Kou", "Petr Kozelka"]);
+            }
+            _ => panic!(""),
+        }
+        let commit = cmp.analyze("Update CI\n\nChangelog: skip");
+        assert!(matches!(commit, CommitMessage::Skipped { .. }));
+
+        let config = ChangeLogConfig::parse_embedded(
+            "<!-- CHANGELOG-CONFIG\n[trailers]\nNo-Changelog = \"skip\"\n-->",
+        )
+        .unwrap();
+        let cmp = CommitMessageAnalyzer::from_config(&config).unwrap();
+        let commit = cmp.analyze("Update CI\n\nNo-Changelog: yes");
+        assert!(matches!(commit, CommitMessage::Skipped { .. }));
+        let commit = cmp.analyze("Update CI\n\nNo-Changelog: false");
+        assert!(matches!(commit, CommitMessage::Contribution { .. }));
+    }
+
     #[test]
     fn rls_commit() {
         let cmp = CommitMessageAnalyzer::init().unwrap();
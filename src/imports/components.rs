@@ -0,0 +1,97 @@
+use glob::{MatchOptions, Pattern};
+
+use crate::error::ChgError;
+use crate::ChangeLogConfig;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Assigns components to commits, based on the files they touch.
+pub struct ComponentResolver {
+    patterns: Vec<(Pattern, String)>,
+}
+
+impl ComponentResolver {
+    pub fn from_config(config: &ChangeLogConfig) -> Result<Self, ChgError> {
+        let mut patterns = Vec::new();
+        for (glob, component) in &config.components {
+            let pattern = Pattern::new(glob)
+                .map_err(|e| ChgError::InvalidComponentPattern(glob.clone(), e.to_string()))?;
+            patterns.push((pattern, component.clone()));
+        }
+        Ok(Self { patterns })
+    }
+
+    /// True if there is no path mapping configured, so that diffing commits can be skipped.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Lists components of given paths, each only once, in the order of first occurrence.
+    /// Each path is assigned to the first matching pattern.
+    pub fn resolve_paths<S: AsRef<str>>(&self, paths: &[S]) -> Vec<String> {
+        let mut components: Vec<String> = Vec::new();
+        for path in paths {
+            let found = self
+                .patterns
+                .iter()
+                .find(|(pattern, _)| pattern.matches_with(path.as_ref(), MATCH_OPTIONS));
+            if let Some((_, component)) = found {
+                if !components.contains(component) {
+                    components.push(component.clone());
+                }
+            }
+        }
+        components
+    }
+
+    /// Splits the subject prefix like `[cpp] some change` into component and the rest.
+    pub fn from_subject(subject: &str) -> Option<(String, String)> {
+        let subject = subject.trim_start();
+        if !subject.starts_with('[') {
+            return None;
+        }
+        let end = subject.find(']')?;
+        let component = subject[1..end].trim();
+        if component.is_empty() || component.contains(char::is_whitespace) {
+            return None;
+        }
+        Some((component.to_string(), subject[end + 1..].trim_start().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::imports::components::ComponentResolver;
+    use crate::ChangeLogConfig;
+
+    #[test]
+    fn resolve_paths() {
+        let mut config = ChangeLogConfig::default();
+        config
+            .components
+            .insert("src/java/**".to_string(), "java".to_string());
+        config.components.insert("py/**".to_string(), "py".to_string());
+        let resolver = ComponentResolver::from_config(&config).unwrap();
+        let components = resolver.resolve_paths(&[
+            "py/daimojo/__init__.py",
+            "README.md",
+            "src/java/ai/h2o/Mojo.java",
+            "py/setup.py",
+        ]);
+        assert_eq!(components, vec!["py", "java"]);
+    }
+
+    #[test]
+    fn from_subject() {
+        let (component, subject) =
+            ComponentResolver::from_subject("[cpp] disable tree shap computing").unwrap();
+        assert_eq!(component, "cpp");
+        assert_eq!(subject, "disable tree shap computing");
+        assert!(ComponentResolver::from_subject("no component here").is_none());
+        assert!(ComponentResolver::from_subject("[not a component] text").is_none());
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::api::Reference;
+use crate::error::ChgError;
+use crate::ChangeLogConfig;
+
+/// What we can learn about a PR or issue from its hosting service.
+#[derive(Debug, Clone, Default)]
+pub struct RefMetadata {
+    pub title: String,
+    pub labels: Vec<String>,
+    /// author handle, like `@pkozelka`
+    pub author: Option<String>,
+    /// PR or MR, whose title and author describe the change; issue authors only reported it
+    pub pull_request: bool,
+}
+
+/// Source of PR and issue metadata.
+///
+/// PRs and issues share the numbering, so the lookup is by number only.
+pub trait MetadataProvider {
+    /// Returns `None` when the number is unknown to the provider.
+    fn lookup(&self, number: u64) -> Result<Option<RefMetadata>, ChgError>;
+}
+
+/// Issue or PR record, as returned by Github REST API
+#[derive(Deserialize)]
+struct GithubIssue {
+    number: u64,
+    title: String,
+    #[serde(default)]
+    labels: Vec<GithubLabel>,
+    user: Option<GithubUser>,
+    /// present on PRs, which the issues API returns too
+    pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct GithubLabel {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GithubUser {
+    login: String,
+}
+
+impl From<GithubIssue> for RefMetadata {
+    fn from(issue: GithubIssue) -> Self {
+        Self {
+            title: issue.title,
+            labels: issue.labels.into_iter().map(|l| l.name).collect(),
+            author: issue.user.map(|u| format!("@{}", u.login)),
+            pull_request: issue.pull_request.is_some(),
+        }
+    }
+}
+
+/// Offline provider, reading a JSON dump of issues and PRs.
+///
+/// Accepts the output of `gh api repos/OWNER/REPO/issues?state=all --paginate`, which is a sequence
+/// of JSON arrays; single objects are accepted as well.
+pub struct JsonCacheProvider {
+    records: HashMap<u64, RefMetadata>,
+}
+
+impl JsonCacheProvider {
+    pub fn parse(text: &str) -> Result<Self, ChgError> {
+        let mut records = HashMap::new();
+        let stream = serde_json::Deserializer::from_str(text).into_iter::<serde_json::Value>();
+        for value in stream {
+            let value = value.map_err(|e| ChgError::MetadataError(e.to_string()))?;
+            let values = match value {
+                serde_json::Value::Array(values) => values,
+                value => vec![value],
+            };
+            for value in values {
+                let issue: GithubIssue = serde_json::from_value(value)
+                    .map_err(|e| ChgError::MetadataError(e.to_string()))?;
+                records.insert(issue.number, issue.into());
+            }
+        }
+        Ok(Self { records })
+    }
+
+    pub fn load<P: AsRef<Path>>(file: P) -> Result<Self, ChgError> {
+        let text = std::fs::read_to_string(file)?;
+        Self::parse(&text)
+    }
+}
+
+impl MetadataProvider for JsonCacheProvider {
+    fn lookup(&self, number: u64) -> Result<Option<RefMetadata>, ChgError> {
+        Ok(self.records.get(&number).cloned())
+    }
+}
+
+/// Online provider, asking Github-compatible REST API at `{api_url}/issues/{number}`.
+/// Token from `GITHUB_TOKEN` environment variable is used when present.
+#[cfg(feature = "rest")]
+pub struct RestProvider {
+    api_url: String,
+    token: Option<String>,
+    cache: std::cell::RefCell<HashMap<u64, Option<RefMetadata>>>,
+}
+
+#[cfg(feature = "rest")]
+impl RestProvider {
+    pub fn new(api_url: &str) -> Self {
+        Self {
+            api_url: api_url.trim_end_matches('/').to_string(),
+            token: std::env::var("GITHUB_TOKEN").ok(),
+            cache: Default::default(),
+        }
+    }
+
+    fn fetch(&self, number: u64) -> Result<Option<RefMetadata>, ChgError> {
+        let url = format!("{}/issues/{}", self.api_url, number);
+        debug!("Fetching {}", url);
+        let mut request = attohttpc::get(&url)
+            .header("Accept", "application/vnd.github.v3+json")
+            .header("User-Agent", "chg");
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+        let response = request
+            .send()
+            .map_err(|e| ChgError::MetadataError(format!("{}: {}", url, e)))?;
+        if response.status() == attohttpc::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.is_success() {
+            return Err(ChgError::MetadataError(format!("{}: {}", url, response.status())));
+        }
+        let issue: GithubIssue = response
+            .json()
+            .map_err(|e| ChgError::MetadataError(format!("{}: {}", url, e)))?;
+        Ok(Some(issue.into()))
+    }
+}
+
+#[cfg(feature = "rest")]
+impl MetadataProvider for RestProvider {
+    fn lookup(&self, number: u64) -> Result<Option<RefMetadata>, ChgError> {
+        if let Some(cached) = self.cache.borrow().get(&number) {
+            return Ok(cached.clone());
+        }
+        let metadata = self.fetch(number)?;
+        self.cache.borrow_mut().insert(number, metadata.clone());
+        Ok(metadata)
+    }
+}
+
+/// Enrichment stage of the import; asks providers, in configured order, about item refs.
+pub struct Enricher {
+    providers: Vec<Box<dyn MetadataProvider>>,
+}
+
+impl Enricher {
+    pub fn new(providers: Vec<Box<dyn MetadataProvider>>) -> Self {
+        Self { providers }
+    }
+
+    /// Creates providers per `[metadata]` config: the cache file first, then the REST API.
+    /// Relative cache file path is resolved against `base_dir`.
+    pub fn from_config(config: &ChangeLogConfig, base_dir: &Path) -> Result<Self, ChgError> {
+        let mut providers: Vec<Box<dyn MetadataProvider>> = Vec::new();
+        let metadata = &config.metadata;
+        if !metadata.cache_file.is_empty() {
+            let file = base_dir.join(&metadata.cache_file);
+            providers.push(Box::new(JsonCacheProvider::load(file)?));
+        }
+        if !metadata.api_url.is_empty() {
+            #[cfg(feature = "rest")]
+            providers.push(Box::new(RestProvider::new(&metadata.api_url)));
+            #[cfg(not(feature = "rest"))]
+            warn!(
+                "Ignoring metadata API '{}' - built without the 'rest' feature",
+                metadata.api_url
+            );
+        }
+        Ok(Self::new(providers))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Finds metadata for the first ref that any provider knows; PRs are tried before issues.
+    /// Metadata found for a PR ref is always considered PR metadata.
+    pub fn enrich(&self, refs: &[Reference]) -> Result<Option<RefMetadata>, ChgError> {
+        let is_pr = |r: &&Reference| matches!(r, Reference::PullRequest { .. });
+        let prs = refs.iter().filter(is_pr);
+        let issues = refs.iter().filter(|r| !is_pr(r));
+        for reference in prs.chain(issues) {
+            let number = match ref_number(reference) {
+                None => continue,
+                Some(number) => number,
+            };
+            for provider in &self.providers {
+                if let Some(mut metadata) = provider.lookup(number)? {
+                    metadata.pull_request |= is_pr(&reference);
+                    return Ok(Some(metadata));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Extracts the number from refs like `PR#123`, `!123` or `#456`; refs to other projects have none.
+fn ref_number(reference: &Reference) -> Option<u64> {
+    match reference {
+        Reference::Issue { project, number }
+        | Reference::PullRequest {
+            project, number, ..
+        } if project.is_empty() => Some(*number),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::Reference;
+    use crate::imports::enrich::{Enricher, JsonCacheProvider};
+
+    const GH_DUMP: &str = r##"[
+        {"number": 979, "title": "not throw exception from daimojo package", "user": {"login": "thirdwing"},
+         "labels": [{"name": "bug"}], "pull_request": {"url": "..."}},
+        {"number": 977, "title": "daimojo throws on import", "user": {"login": "someone"}}
+    ][
+        {"number": 1073, "title": "disable tree shap computing", "labels": []}
+    ]"##;
+
+    #[test]
+    fn cache_paginated_dump() {
+        let provider = JsonCacheProvider::parse(GH_DUMP).unwrap();
+        let enricher = Enricher::new(vec![Box::new(provider)]);
+        let metadata = enricher
+            .enrich(&[Reference::parse("#977"), Reference::parse("PR#979")])
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.title, "not throw exception from daimojo package");
+        assert_eq!(metadata.labels, vec!["bug"]);
+        assert_eq!(metadata.author.as_deref(), Some("@thirdwing"));
+        assert!(metadata.pull_request);
+        let metadata = enricher
+            .enrich(&[Reference::parse("#977")])
+            .unwrap()
+            .unwrap();
+        assert!(!metadata.pull_request);
+
+        let metadata = enricher
+            .enrich(&[Reference::parse("PR#1073")])
+            .unwrap()
+            .unwrap();
+        assert!(metadata.author.is_none());
+        assert!(metadata.pull_request);
+        assert!(enricher
+            .enrich(&[Reference::parse("PR#1")])
+            .unwrap()
+            .is_none());
+    }
+}
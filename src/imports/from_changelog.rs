@@ -1,9 +1,10 @@
 use chrono::NaiveDate;
 use regex::Regex;
 
-use crate::api::{ChangeItem, ChangeType, ReleaseHeader};
+use crate::api::{ChangeItem, ChangeType, Reference, ReleaseHeader};
 use crate::builder::ChangeLogBuilder;
 use crate::error::ChgError;
+use crate::layout::MarkdownLayout;
 use crate::{ChangeLog, ChangeLogConfig};
 
 enum ParserState {
@@ -15,6 +16,11 @@ enum ParserState {
 impl ChangeLog {
     pub fn import_markdown(text: &str) -> Result<ChangeLog, ChgError> {
         let config = ChangeLogConfig::parse_embedded(&text)?;
+        Self::import_markdown_with(text, config)
+    }
+
+    /// Parses the changelog with given config, like one from [`ConfigLayers`](crate::discovery::ConfigLayers).
+    pub fn import_markdown_with(text: &str, config: ChangeLogConfig) -> Result<ChangeLog, ChgError> {
         let mut builder = ChangeLogBuilder::new(config);
         builder.parse(&text)?;
         Ok(builder.build())
@@ -22,35 +28,72 @@ impl ChangeLog {
 }
 
 impl ChangeLogBuilder {
+    /// Parses markdown per layout from `[templates]` config.
     pub fn parse(&mut self, reader: &str) -> Result<(), ChgError> {
+        let layout = MarkdownLayout::from_config(self.config())?;
         let lines = reader.lines();
         let mut state = ParserState::Prolog;
+        let mut change_type = ChangeType::Other;
         // read prolog
         for line in lines {
+            // item details are indented
+            let indented = line.starts_with("  ");
             let line = line.trim().to_string();
             if line.is_empty() {
                 continue;
             }
 
+            // details may contain anything, even lines looking like headings
+            if indented && matches!(state, ParserState::Section) && self.item_details(&line) {
+                continue;
+            }
             match state {
                 ParserState::Prolog | ParserState::Section => {
-                    if line.starts_with("## ") {
-                        self.section(ReleaseHeader::parse_section_header(&line[3..])?);
+                    let header = if line.eq_ignore_ascii_case("## unreleased") {
+                        Some(None)
+                    } else if let Some(header) = layout.parse_heading(&line)? {
+                        Some(Some(header))
+                    } else if let Some(heading) = line.strip_prefix("## ") {
+                        Some(ReleaseHeader::parse_section_header(heading)?)
+                    } else {
+                        None
+                    };
+                    if let Some(header) = header {
+                        self.section(header);
+                        change_type = ChangeType::Other;
                         state = ParserState::Section;
                         continue;
                     }
                 }
                 ParserState::Epilog => { /* until the EOF */ }
             }
+            if !matches!(state, ParserState::Prolog) && layout.is_footer(&line) {
+                // footer is generated from releases
+                continue;
+            }
             match state {
+                ParserState::Section if line.starts_with('>') => {
+                    self.release_notes(line[1..].trim_start());
+                }
                 ParserState::Section => {
-                    let change_item = ChangeItem::parse_item(&line)?;
+                    if let Some(t) = layout.parse_type_heading(&line) {
+                        change_type = t;
+                        continue;
+                    }
+                    let change_item = if layout.has_custom_item() {
+                        layout.parse_item(&line)
+                    } else {
+                        ChangeItem::parse_item(&line)?
+                    };
                     match change_item {
                         None => {
                             self.note(&line)?;
                             state = ParserState::Epilog;
                         }
-                        Some(change_item) => {
+                        Some(mut change_item) => {
+                            if change_item.change_type == ChangeType::Other {
+                                change_item.change_type = change_type;
+                            }
                             self.item(change_item)?;
                         }
                     }
@@ -68,21 +111,33 @@ impl ChangeItem {
     fn parse_item(s: &str) -> Result<Option<Self>, ChgError> {
         if s.starts_with("- ") || s.starts_with("* ") {
             let r = Regex::new(
-                "((?P<refs>.*?):)?\\s*(?P<compo>\\[\\S+])?\\s*(?P<text>.*)/(?P<authors>.*)$",
+                "((?P<refs>.*?):\\s)?\\s*(?P<compo>\\[\\S+])?\\s*(?P<text>.*)/(?P<authors>.*)$",
             )
             .unwrap();
             let s = &s[2..];
+            // source commit, as trailing `<!-- abc1234 -->`, `(abc1234)` or `([abc1234](link))`
+            let commit_regex = Regex::new(
+                r"\s*(<!--\s*(?P<hidden>[0-9a-f]{7,40})\s*-->|\((\[(?P<linked>[0-9a-f]{7,40})]\(\S*\)|(?P<plain>[0-9a-f]{7,40}))\))$",
+            )
+            .unwrap();
+            let (s, commit) = match commit_regex.captures(s) {
+                None => (s, None),
+                Some(captures) => {
+                    let commit = captures
+                        .name("hidden")
+                        .or_else(|| captures.name("linked"))
+                        .or_else(|| captures.name("plain"))
+                        .map(|m| m.as_str().to_string());
+                    (&s[0..captures.get(0).unwrap().start()], commit)
+                }
+            };
             let captures = match r.captures(s) {
                 None => panic!("Invalid item line: '{}'", s),
                 Some(c) => c,
             };
-            let refs: Vec<String> = match captures.name("refs") {
+            let refs: Vec<Reference> = match captures.name("refs") {
                 None => Vec::new(),
-                Some(refs) => refs
-                    .as_str()
-                    .split(",")
-                    .map(|s| s.trim().to_string())
-                    .collect(),
+                Some(refs) => refs.as_str().split(",").map(Reference::parse).collect(),
             };
             let component = captures
                 .name("compo")
@@ -105,6 +160,9 @@ impl ChangeItem {
                 component,
                 text,
                 authors,
+                commit,
+                details: "".to_string(),
+                type_confidence: None,
             };
             Ok(Some(chgi))
         } else {
@@ -122,6 +180,7 @@ impl ReleaseHeader {
     /// * `1.2.3-1 2020-04-20`
     /// * `1.2.333 2020-04-20 yanked`
     /// * `1.2.3.b5.c7-a 2020-04-20 yanked`
+    /// * `2.5.12 - 2020-11-02 (maint-2.5)`, released from a maintenance branch
     /// * `Unreleased`
     fn parse_section_header(s: &str) -> Result<Option<Self>, ChgError> {
         let s = s.trim();
@@ -161,19 +220,28 @@ impl ReleaseHeader {
 
             // - parse timestamp
             let r = Regex::new("(?P<timestamp>\\d+-\\d+-\\d+)$").unwrap();
-            let timestamp = r.captures(timestamp)
+            let timestamp = r
+                .captures(timestamp)
                 .and_then(|captures| captures.name("timestamp"))
                 .and_then(|m| Some(m.as_str()))
-                .ok_or(ChgError::InvalidTimestamp(timestamp.to_owned(), s.to_owned()))?;
+                .ok_or(ChgError::InvalidTimestamp(
+                    timestamp.to_owned(),
+                    s.to_owned(),
+                ))?;
             let timestamp = NaiveDate::parse_from_str(timestamp, "%Y-%m-%d")
                 .or_else(|e| Err(ChgError::InvalidTimestamp(s.to_owned(), e.to_string())))?;
 
-            // yanked
-            let yanked = if let Some(more) = section_tokens.next() {
-                more.to_ascii_uppercase().contains("YANKED")
-            } else {
-                false
-            };
+            // branch and yanked
+            let mut branch = "";
+            let mut yanked = false;
+            for more in section_tokens {
+                let name = more.strip_prefix('(').and_then(|m| m.strip_suffix(')'));
+                if let Some(name) = name.filter(|name| !name.is_empty()) {
+                    branch = name;
+                } else if more.to_ascii_uppercase().contains("YANKED") {
+                    yanked = true;
+                }
+            }
 
             //
             Ok(Some(ReleaseHeader {
@@ -181,6 +249,7 @@ impl ReleaseHeader {
                 tag: "".to_string(),
                 timestamp,
                 yanked,
+                branch: branch.to_string(),
             }))
         }
     }
@@ -191,6 +260,7 @@ mod tests {
     use chrono::NaiveDate;
 
     use crate::api::{ChangeItem, ChangeType, ReleaseHeader};
+    use crate::ChangeLog;
 
     #[test]
     fn test_parse_section_header_unreleased() {
@@ -208,6 +278,7 @@ mod tests {
                 tag,
                 timestamp,
                 yanked,
+                ..
             }) => {
                 assert_eq!(version, "2.5.6", "version");
                 assert_eq!(tag, "", "tag");
@@ -220,7 +291,8 @@ mod tests {
 
     #[test]
     fn test_parse_section_header_released_noseparator_yanked() {
-        let header = ReleaseHeader::parse_section_header("1.22.333-alpha-1 2021-04-20 YANKED").unwrap();
+        let header =
+            ReleaseHeader::parse_section_header("1.22.333-alpha-1 2021-04-20 YANKED").unwrap();
         match header {
             None => panic!("Release expected here"),
             Some(ReleaseHeader {
@@ -228,6 +300,7 @@ mod tests {
                 tag,
                 timestamp,
                 yanked,
+                ..
             }) => {
                 assert_eq!(version, "1.22.333-alpha-1", "version");
                 assert_eq!(tag, "", "tag");
@@ -238,6 +311,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_section_header_branch() {
+        let header =
+            ReleaseHeader::parse_section_header("2.5.12 - 2020-11-02 (maint-2.5) [YANKED]")
+                .unwrap()
+                .unwrap();
+        assert_eq!(header.version, "2.5.12");
+        assert_eq!(header.branch, "maint-2.5");
+        assert!(header.yanked);
+        let header = ReleaseHeader::parse_section_header("2.5.6 - 2020-12-10")
+            .unwrap()
+            .unwrap();
+        assert_eq!(header.branch, "");
+    }
+
     #[test]
     fn test_parse_item() {
         let item = ChangeItem::parse_item(
@@ -253,5 +341,47 @@ mod tests {
         assert_eq!(item.text, "parse the UUID of mojo. close #628");
         assert_eq!(item.authors.len(), 1, "Authors: {:?}", item.authors);
         assert_eq!(item.authors[0], "Qiang Kou", "Authors: {:?}", item.authors);
+        assert!(item.commit.is_none());
+    }
+
+    #[test]
+    fn test_parse_release_notes() {
+        let text = "# Changelog\n\n## 1.0.1 - 2020-09-15\n\n> Hotfix release.\n>\n> Upgrade recommended.\n\n- Fix NPE / pk\n\n";
+        let changelog = ChangeLog::import_markdown(text).unwrap();
+        let changeset = &changelog.releases[0].1;
+        assert_eq!(changeset.notes, "Hotfix release.\n\nUpgrade recommended.");
+        assert_eq!(changeset.items.len(), 1);
+
+        let mut out = Vec::new();
+        changelog.print_markdown(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), text);
+    }
+
+    #[test]
+    fn test_parse_item_commit() {
+        let item = ChangeItem::parse_item("- Fix NPE / Qiang Kou <!-- 1a2b3c4 -->")
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.commit.as_deref(), Some("1a2b3c4"));
+        assert_eq!(item.authors, vec!["Qiang Kou"]);
+        let item = ChangeItem::parse_item(
+            "- PR#629: Fix NPE / Qiang Kou ([1a2b3c4](https://github.com/o/r/commit/1a2b3c4d5e))",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(item.commit.as_deref(), Some("1a2b3c4"));
+        assert_eq!(item.text, "Fix NPE");
+        assert_eq!(item.authors, vec!["Qiang Kou"]);
+    }
+
+    #[test]
+    fn test_parse_details_like_heading() {
+        let changelog = ChangeLog::import_markdown(
+            "# Changelog\n## Unreleased\n- Fix NPE / pk\n  ## 2.0.0 - 2020-10-01\n  more\n## 1.0.0 - 2020-09-15\n",
+        )
+        .unwrap();
+        let items = &changelog.unreleased.as_ref().unwrap().items;
+        assert_eq!(items[0].details, "## 2.0.0 - 2020-10-01\nmore");
+        assert_eq!(changelog.releases.len(), 1);
     }
 }
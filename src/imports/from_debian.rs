@@ -0,0 +1,257 @@
+use std::sync::OnceLock;
+
+use chrono::DateTime;
+use regex::Regex;
+
+use crate::api::{ChangeItem, ChangeType, Reference, ReleaseHeader};
+use crate::builder::ChangeLogBuilder;
+use crate::{ChangeLog, ChangeLogConfig, ChgError};
+
+/// One `debian/changelog` entry, collected until its trailer line brings the date.
+struct DebianEntry {
+    version: String,
+    unreleased: bool,
+    notes: Vec<String>,
+    items: Vec<ChangeItem>,
+}
+
+impl ChangeLog {
+    /// Parses `debian/changelog` content, so that it can be synced with another changelog.
+    ///
+    /// Entries for the `UNRELEASED` distribution form the `Unreleased` section.
+    /// Item authors come from `[ Name ]` group lines, or from the entry trailer.
+    pub fn import_debian(text: &str, config: ChangeLogConfig) -> Result<ChangeLog, ChgError> {
+        let header_regex =
+            Regex::new(r"^(?P<package>\S+) \((?P<version>[^)]+)\) (?P<distribution>[^;]+);")
+                .unwrap();
+        let trailer_regex = Regex::new(r"^ -- (?P<name>.*?)\s*(<[^>]*>)?  (?P<date>.+)$").unwrap();
+        let group_regex = Regex::new(r"^  \[ (?P<name>.+) ]$").unwrap();
+        let mut builder = ChangeLogBuilder::new(config);
+        let mut entry: Option<DebianEntry> = None;
+        let mut group_author: Option<String> = None;
+        for line in text.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(captures) = header_regex.captures(line) {
+                if entry.is_some() {
+                    return Err(ChgError::InvalidDebianChangelog(line.to_string()));
+                }
+                entry = Some(DebianEntry {
+                    version: upstream_version(&captures["version"]),
+                    unreleased: captures["distribution"].trim() == "UNRELEASED",
+                    notes: vec![],
+                    items: vec![],
+                });
+                group_author = None;
+                continue;
+            }
+            let current = match &mut entry {
+                None => return Err(ChgError::InvalidDebianChangelog(line.to_string())),
+                Some(current) => current,
+            };
+            if let Some(captures) = trailer_regex.captures(line) {
+                let date = DateTime::parse_from_rfc2822(&captures["date"])
+                    .map_err(|e| ChgError::InvalidTimestamp(line.to_string(), e.to_string()))?;
+                let maintainer = captures["name"].to_string();
+                let current = entry.take().unwrap();
+                if current.unreleased {
+                    builder.section(None);
+                } else {
+                    let header = ReleaseHeader::release_tagged(
+                        "",
+                        &current.version,
+                        date.naive_local().date(),
+                        false,
+                    );
+                    builder.section(Some(header));
+                }
+                builder.release_notes(&current.notes.join("\n"));
+                for mut item in current.items {
+                    if item.authors.is_empty() {
+                        item.authors.push(maintainer.clone());
+                    }
+                    builder.item(item)?;
+                }
+            } else if let Some(captures) = group_regex.captures(line) {
+                group_author = Some(captures["name"].to_string());
+            } else if let Some(text) = line.strip_prefix("  * ") {
+                let item = parse_item(text, group_author.as_deref(), builder.config());
+                current.items.push(item);
+            } else if let Some(text) = line.strip_prefix("    ") {
+                match current.items.last_mut() {
+                    None => return Err(ChgError::InvalidDebianChangelog(line.to_string())),
+                    Some(item) => {
+                        item.text.push(' ');
+                        item.text.push_str(text.trim());
+                    }
+                }
+            } else if let Some(text) = line.strip_prefix("  ") {
+                current.notes.push(text.to_string());
+            } else {
+                return Err(ChgError::InvalidDebianChangelog(line.to_string()));
+            }
+        }
+        if entry.is_some() {
+            return Err(ChgError::InvalidDebianChangelog(
+                "missing trailer of last entry".to_string(),
+            ));
+        }
+        let mut changelog = builder.build();
+        let config = changelog.config.clone();
+        // refs end up inside the text when they are on a continuation line
+        for changeset in changelog.unreleased.iter_mut().chain(
+            changelog
+                .releases
+                .iter_mut()
+                .map(|(_, changeset)| changeset),
+        ) {
+            for item in &mut changeset.items {
+                if item.refs.is_empty() {
+                    let (text, refs) = split_refs(&item.text, &config);
+                    item.text = text;
+                    item.refs = refs;
+                }
+            }
+        }
+        Ok(changelog)
+    }
+}
+
+/// Strips epoch and Debian revision, and turns `~` back into pre-release separator: `1:1.0.0~rc1-2` is `1.0.0-rc1`.
+fn upstream_version(version: &str) -> String {
+    let version = match version.find(':') {
+        None => version,
+        Some(colon) => &version[colon + 1..],
+    };
+    let version = match version.rfind('-') {
+        None => version,
+        Some(dash) => &version[0..dash],
+    };
+    version.replace('~', "-")
+}
+
+fn parse_item(text: &str, author: Option<&str>, config: &ChangeLogConfig) -> ChangeItem {
+    static COMPONENT: OnceLock<Regex> = OnceLock::new();
+    let component_regex =
+        COMPONENT.get_or_init(|| Regex::new(r"^\[(?P<component>[^\]]+)]\s+").unwrap());
+    let (text, component) = match component_regex.captures(text) {
+        None => (text, ""),
+        Some(captures) => (
+            &text[captures.get(0).unwrap().end()..],
+            captures.name("component").unwrap().as_str(),
+        ),
+    };
+    let (text, refs) = split_refs(text, config);
+    ChangeItem {
+        refs,
+        change_type: ChangeType::Other,
+        component: component.to_string(),
+        text,
+        authors: author.map(str::to_string).into_iter().collect(),
+        commit: None,
+        details: String::new(),
+        type_confidence: None,
+    }
+}
+
+/// Splits trailing refs like `(PR#12, !3, group/project#4, PROJ-5)` from the text;
+/// parentheses with anything else than refs are part of the text.
+fn split_refs(text: &str, config: &ChangeLogConfig) -> (String, Vec<Reference>) {
+    static REFS: OnceLock<Regex> = OnceLock::new();
+    let regex =
+        REFS.get_or_init(|| Regex::new(r"\s*\((?P<refs>[^()\s,]+(?:,\s*[^()\s,]+)*)\)$").unwrap());
+    let captures = match regex.captures(text) {
+        None => return (text.to_string(), vec![]),
+        Some(captures) => captures,
+    };
+    let refs: Option<Vec<Reference>> = captures["refs"]
+        .split(',')
+        .map(|r| parse_ref(r.trim(), config))
+        .collect();
+    match refs {
+        None => (text.to_string(), vec![]),
+        Some(refs) => (text[0..captures.get(0).unwrap().start()].to_string(), refs),
+    }
+}
+
+/// Reference, unless it is just a word: tracker keys have to look like `PROJ-12`, or match a configured tracker.
+fn parse_ref(text: &str, config: &ChangeLogConfig) -> Option<Reference> {
+    static TRACKER_KEY: OnceLock<Regex> = OnceLock::new();
+    let key_regex = TRACKER_KEY.get_or_init(|| Regex::new(r"^[A-Z][A-Z0-9_]*-\d+$").unwrap());
+    match config.parse_reference(text) {
+        Reference::ExternalTracker(key)
+            if config.tracker_of(&key).is_none() && !key_regex.is_match(&key) =>
+        {
+            None
+        }
+        reference => Some(reference),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChangeLog, ChangeLogConfig};
+
+    #[test]
+    fn parse_debian() {
+        let text = "mojo (2:1.2.0-3) UNRELEASED; urgency=low
+
+  * Work in progress
+
+ -- Jane Doe <jane@example.com>  Fri, 02 Oct 2020 10:00:00 +0200
+
+mojo (2:1.1.0-3) unstable; urgency=low
+
+  [ John Smith ]
+  * Fix crash in parser
+    on empty input (#7)
+
+  [ Jane Doe ]
+  * Closes: #123
+
+ -- Jane Doe <jane@example.com>  Thu, 01 Oct 2020 23:30:00 -0200
+";
+        let changelog = ChangeLog::import_debian(text, ChangeLogConfig::default()).unwrap();
+        let unreleased = changelog.unreleased.as_ref().unwrap();
+        assert_eq!(unreleased.items[0].text, "Work in progress");
+        let (header, changeset) = &changelog.releases[0];
+        assert_eq!(header.version, "1.1.0");
+        assert_eq!(header.timestamp.to_string(), "2020-10-01");
+        assert_eq!(
+            changeset.items[0].text,
+            "Fix crash in parser on empty input"
+        );
+        assert_eq!(changeset.items[0].refs, vec!["#7"]);
+        assert_eq!(changeset.items[0].authors, vec!["John Smith"]);
+        assert_eq!(changeset.items[1].text, "Closes: #123");
+        assert_eq!(changeset.items[1].authors, vec!["Jane Doe"]);
+    }
+
+    #[test]
+    fn debian_roundtrip_refs() {
+        let markdown = r#"# Changelog
+<!-- CHANGELOG-CONFIG
+[debian]
+package = "mojo"
+maintainer = "Jane Doe <jane@example.com>"
+-->
+
+## 1.0.0 - 2020-10-01
+
+- !5, group/proj#9, PROJ-12: Fix crash / pk
+- Parser tweaks (experimental) / pk
+"#;
+        let changelog = ChangeLog::import_markdown(markdown).unwrap();
+        let mut out = Vec::new();
+        changelog.print_debian(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let imported = ChangeLog::import_debian(&text, changelog.config.clone()).unwrap();
+        let items = &imported.releases[0].1.items;
+        assert_eq!(items[0].text, "Fix crash", "{}", text);
+        assert_eq!(items[0].refs, vec!["!5", "group/proj#9", "PROJ-12"]);
+        assert_eq!(items[1].text, "Parser tweaks (experimental)");
+        assert!(items[1].refs.is_empty());
+    }
+}
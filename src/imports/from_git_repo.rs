@@ -1,13 +1,27 @@
-use crate::api::{ChangeItem, ChangeType, ReleaseHeader};
+use crate::api::{ChangeItem, Reference, ReleaseHeader};
 use crate::builder::ChangeLogBuilder;
+use crate::imports::change_type::{ChangeTypeClassifier, Classification, Confidence};
 use crate::imports::commit_msg::{CommitMessage, CommitMessageAnalyzer};
-use crate::{ChangeLog, ChangeLogConfig};
+use crate::imports::components::ComponentResolver;
+use crate::imports::enrich::Enricher;
+use crate::{ChangeLog, ChangeLogConfig, ChgError};
 use chrono::NaiveDate;
-use git2::{Error, Oid, Repository};
-use std::collections::HashMap;
+use git2::{BranchType, Commit, Error, ObjectType, Oid, Repository, Sort};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::str::FromStr;
 
-fn list_tags(repo: &Repository) -> Result<HashMap<Oid, String>, Error> {
+/// Tag pointing to a commit; annotated tags also bring their message and date.
+struct TagInfo {
+    name: String,
+    /// message of annotated tag, without signature
+    message: Option<String>,
+    /// tagger date of annotated tag
+    date: Option<NaiveDate>,
+}
+
+fn list_tags(repo: &Repository) -> Result<HashMap<Oid, TagInfo>, Error> {
     let mut tag_objects: HashMap<Oid, String> = HashMap::new();
     repo.tag_foreach(|oid, bytes| {
         let ref_name = String::from_utf8_lossy(bytes);
@@ -21,104 +35,527 @@ fn list_tags(repo: &Repository) -> Result<HashMap<Oid, String>, Error> {
     })?;
 
     let mut tags = HashMap::new();
-    for (oid, tag_name) in tag_objects {
+    for (oid, name) in tag_objects {
         match repo.find_tag(oid) {
             Ok(heavy_tag) => {
                 // Heavy tag: oid is stored inside the object
-                let oid = heavy_tag.target()?.id();
-                tags.insert(oid, tag_name);
+                let oid = heavy_tag.target()?.peel(ObjectType::Commit)?.id();
+                let message =
+                    heavy_tag
+                        .message()
+                        .map(|m| match m.find("-----BEGIN PGP SIGNATURE-----") {
+                            None => m.to_string(),
+                            Some(signature) => m[0..signature].to_string(),
+                        });
+                let date = heavy_tag
+                    .tagger()
+                    .map(|tagger| git_time_to_chrono(tagger.when()));
+                tags.insert(
+                    oid,
+                    TagInfo {
+                        name,
+                        message,
+                        date,
+                    },
+                );
             }
             Err(_) => {
                 // Lightweight tag: its oid equals target
-                tags.insert(oid, tag_name);
+                tags.insert(
+                    oid,
+                    TagInfo {
+                        name,
+                        message: None,
+                        date: None,
+                    },
+                );
             }
         }
     }
     Ok(tags)
 }
 
+/// Lists paths of files changed by the commit, compared to its first parent.
+fn changed_paths(repo: &Repository, commit: &Commit) -> Result<Vec<String>, Error> {
+    let tree = commit.tree()?;
+    let parent_tree = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+    let mut paths = Vec::new();
+    for delta in diff.deltas() {
+        for file in &[delta.new_file(), delta.old_file()] {
+            if let Some(path) = file.path().and_then(|p| p.to_str()) {
+                if !paths.iter().any(|p| p == path) {
+                    paths.push(path.to_string());
+                }
+            }
+        }
+    }
+    Ok(paths)
+}
+
+/// Tools for interpreting commits, configured once per import.
+struct CommitInterpreter {
+    traversal: Traversal,
+    analyzer: CommitMessageAnalyzer,
+    classifier: ChangeTypeClassifier,
+    components: ComponentResolver,
+    enricher: Enricher,
+    /// include commit body in items
+    details: bool,
+}
+
+impl CommitInterpreter {
+    fn new(
+        config: &ChangeLogConfig,
+        repo: &Repository,
+        options: &GitImportOptions,
+    ) -> Result<Self, ChgError> {
+        let traversal = match options.traversal {
+            Some(traversal) => traversal,
+            None => Traversal::from_str(&config.git.traversal)?,
+        };
+        Ok(Self {
+            traversal,
+            analyzer: CommitMessageAnalyzer::from_config(config)?,
+            classifier: ChangeTypeClassifier::from_config(config)?,
+            components: ComponentResolver::from_config(config)?,
+            enricher: Enricher::from_config(config, repo.workdir().unwrap_or_else(|| repo.path()))?,
+            details: config.git.details,
+        })
+    }
+
+    /// Analyzes the commit; returns `None` for commits that carry no change on their own.
+    fn interpret(
+        &self,
+        repo: &Repository,
+        commit: &Commit,
+    ) -> Result<Option<(CommitInfo, CommitMessage)>, Error> {
+        let msg = commit.message().unwrap_or("");
+        let cm = self.analyzer.analyze(msg);
+        let is_merge = commit.parent_count() > 1;
+        if is_merge && self.traversal == Traversal::Topological && !cm.has_refs() {
+            trace!("Skipping merge commit {}: {}", commit.id(), msg.trim());
+            return Ok(None);
+        }
+        let author = commit.author();
+        let mut info = CommitInfo {
+            id: commit.id(),
+            author: author.name().unwrap_or("?").to_string(),
+            timestamp: git_time_to_chrono(author.when()),
+            paths: vec![],
+            merged_refs: vec![],
+            merged_authors: vec![],
+        };
+        if !self.components.is_empty() {
+            info.paths = changed_paths(repo, commit)?;
+        }
+        if is_merge && self.traversal == Traversal::MergesCollapsed {
+            collapse_merged(repo, commit, &self.analyzer, &mut info)?;
+        }
+        Ok(Some((info, cm)))
+    }
+}
+
+/// Patch id of the commit, for recognizing cherry-picks; `None` for merges and empty commits.
+fn patch_id(repo: &Repository, commit: &Commit) -> Result<Option<Oid>, Error> {
+    if commit.parent_count() != 1 {
+        return Ok(None);
+    }
+    let parent_tree = commit.parent(0)?.tree()?;
+    let diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&commit.tree()?), None)?;
+    if diff.deltas().len() == 0 {
+        return Ok(None);
+    }
+    Ok(Some(diff.patchid(None)?))
+}
+
+/// Finds the branch containing the commit, other than the main line; local branches are preferred.
+fn branch_containing(repo: &Repository, oid: Oid, visited: &HashSet<Oid>) -> Result<String, Error> {
+    let mut found: Option<String> = None;
+    for branch in repo.branches(None)? {
+        let (branch, branch_type) = branch?;
+        let tip = match branch.get().target() {
+            None => continue,
+            Some(tip) => tip,
+        };
+        if visited.contains(&tip) || !(tip == oid || repo.graph_descendant_of(tip, oid)?) {
+            continue;
+        }
+        if let Some(name) = branch.name()? {
+            if branch_type == BranchType::Local {
+                return Ok(name.to_string());
+            }
+            found.get_or_insert_with(|| name.to_string());
+        }
+    }
+    Ok(found.unwrap_or_default())
+}
+
+/// Compares version strings by their numeric parts, like `2.5.12` > `2.5.9`.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let numbers = |v: &str| -> Vec<u64> {
+        v.split(|c: char| !c.is_ascii_digit())
+            .filter(|n| !n.is_empty())
+            .map(|n| n.parse().unwrap_or(0))
+            .collect()
+    };
+    numbers(a).cmp(&numbers(b))
+}
+
+/// Gathers refs and authors of commits brought in by the merge commit, i.e. reachable from its other parents only.
+fn collapse_merged(
+    repo: &Repository,
+    merge: &Commit,
+    analyzer: &CommitMessageAnalyzer,
+    info: &mut CommitInfo,
+) -> Result<(), Error> {
+    let mut revwalk = repo.revwalk()?;
+    for parent_id in merge.parent_ids().skip(1) {
+        revwalk.push(parent_id)?;
+    }
+    revwalk.hide(merge.parent_id(0)?)?;
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if let CommitMessage::Contribution { refs, .. } =
+            analyzer.analyze(commit.message().unwrap_or(""))
+        {
+            for r in refs {
+                if !info.merged_refs.contains(&r) {
+                    info.merged_refs.push(r);
+                }
+            }
+        }
+        let author = commit.author().name().unwrap_or("?").to_string();
+        if author != info.author && !info.merged_authors.contains(&author) {
+            info.merged_authors.push(author);
+        }
+    }
+    Ok(())
+}
+
 fn git_time_to_chrono(time: git2::Time) -> NaiveDate {
     let offset_seconds = time.offset_minutes() * 60;
     chrono::NaiveDateTime::from_timestamp(time.seconds() + offset_seconds as i64, 0).date()
 }
 
+/// How to walk the git history.
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum Traversal {
+    /// only the main line; merged branches contribute with the merge commit only
+    FirstParent,
+    /// all commits, including those on merged branches; plain merge commits are skipped
+    Topological,
+    /// only the main line, with refs and authors of merged commits collapsed into the merge commit item
+    MergesCollapsed,
+}
+
+impl FromStr for Traversal {
+    type Err = ChgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" | "first-parent" => Ok(Traversal::FirstParent),
+            "topological" => Ok(Traversal::Topological),
+            "merges" => Ok(Traversal::MergesCollapsed),
+            _ => Err(ChgError::UnknownTraversal(s.to_string())),
+        }
+    }
+}
+
+/// What part of the git history to import, and how.
+#[derive(Debug, Default, Clone)]
+pub struct GitImportOptions {
+    /// stop on the release with this version (must match exactly)
+    pub stop_version: Option<String>,
+    /// revision where the history starts, excluded; the very beginning if not set
+    pub from: Option<String>,
+    /// revision where the history ends, included; `HEAD` if not set
+    pub to: Option<String>,
+    /// overrides `git.traversal` from config
+    pub traversal: Option<Traversal>,
+}
+
+/// What the traversal learned about a commit, besides its message.
+struct CommitInfo {
+    id: Oid,
+    author: String,
+    timestamp: NaiveDate,
+    /// changed files; only collected when components are configured
+    paths: Vec<String>,
+    /// refs and authors of commits collapsed into this merge commit
+    merged_refs: Vec<Reference>,
+    merged_authors: Vec<String>,
+}
+
 impl ChangeLogBuilder {
-    /// Traverses commit from current HEAD backwards, until a release commit with `stop_version` is encountered, or until the end.
+    /// Traverses commits from `options.to` (or HEAD) backwards, until a release commit with `stop_version` is encountered,
+    /// or until `options.from`, or until the end. All traversed commits are recorded in `visited`.
     ///
-    /// TODO pre-process tags to
-    /// * a) only contain release tags
-    /// * b) relate them to main path in case they are on release branch (like 2.5.12 in mojo2)
+    /// TODO pre-process tags to only contain release tags.
+    /// Release tags off the traversed line are handled by [`traverse_side_release`](Self::traverse_side_release).
     /// Any release commits with no related tags are considered yanked. So are commits with special yanked tags.
     fn traverse_commits(
         &mut self,
         repo: &Repository,
-        tags: &HashMap<Oid, String>,
-        stop_version: Option<&str>,
-    ) -> Result<(), Error> {
-        let head = repo.head()?;
-        let mut commit = head.peel_to_commit()?;
-        let cma = CommitMessageAnalyzer::init().unwrap();
-        while !commit.id().is_zero() {
-            {
-                let author = commit.author();
-                let ts = git_time_to_chrono(author.when());
-                match tags.get(&commit.id()) {
-                    None => {
-                        let msg = commit.message().unwrap_or("");
-                        let cm = cma.analyze(msg);
-                        if !self.handle_untagged_commit(stop_version, author.name().unwrap_or("?"), ts, cm) {
+        tags: &HashMap<Oid, TagInfo>,
+        options: &GitImportOptions,
+        interpreter: &CommitInterpreter,
+        visited: &mut HashSet<Oid>,
+    ) -> Result<(), ChgError> {
+        let stop_version = options.stop_version.as_deref();
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+        match &options.to {
+            None => revwalk.push_head()?,
+            Some(to) => revwalk.push(repo.revparse_single(to)?.peel_to_commit()?.id())?,
+        }
+        if let Some(from) = &options.from {
+            revwalk.hide(repo.revparse_single(from)?.peel_to_commit()?.id())?;
+        }
+        if interpreter.traversal != Traversal::Topological {
+            revwalk.simplify_first_parent()?;
+        }
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            visited.insert(commit.id());
+            match tags.get(&commit.id()) {
+                None => {
+                    if let Some((info, cm)) = interpreter.interpret(repo, &commit)? {
+                        if !self.handle_untagged_commit(stop_version, info, cm, interpreter)? {
                             break;
                         }
                     }
-                    Some(tag_name) => {
-                        if let Some(version) = self.tag_name_to_version(tag_name) {
-                            let yanked = tag_name.to_uppercase().contains("YANKED"); // TODO: configurable
-                            self.section(Some(ReleaseHeader::release_tagged(
-                                tag_name,
-                                version.as_str(),
-                                ts,
-                                yanked,
-                            )));
-
-                            if let Some(stop_version) = stop_version {
-                                if stop_version == version {
-                                    trace!("Stopping on version '{}' as requested", version);
-                                    break;
-                                }
+                }
+                Some(tag) => {
+                    if let Some(version) = self.release_section(tag, &commit, "") {
+                        if let Some(stop_version) = stop_version {
+                            if stop_version == version {
+                                trace!("Stopping on version '{}' as requested", version);
+                                break;
                             }
                         }
                     }
                 }
             }
-            if commit.parent_count() == 0 { break }
-            commit = commit.parent(0)?;
         }
         Ok(())
     }
 
+    /// Finds release tags that the traversal did not reach, but which are on branches forked from the traversed line,
+    /// like `2.5.12` on a maintenance branch. Returns tagged commits with their fork points.
+    fn side_release_tags(
+        &self,
+        repo: &Repository,
+        tags: &HashMap<Oid, TagInfo>,
+        visited: &HashSet<Oid>,
+    ) -> Result<Vec<(Oid, Oid)>, Error> {
+        let mut side_tags = Vec::new();
+        for (oid, tag) in tags {
+            if visited.contains(oid) || self.tag_name_to_version(&tag.name).is_none() {
+                continue;
+            }
+            // follow first parents down to the traversed line
+            let mut commit = match repo.find_commit(*oid) {
+                Ok(commit) => commit,
+                Err(_) => continue,
+            };
+            while commit.parent_count() > 0 && !visited.contains(&commit.id()) {
+                commit = commit.parent(0)?;
+            }
+            if visited.contains(&commit.id()) {
+                trace!(
+                    "Release tag {} is off the main line, forked at {}",
+                    tag.name,
+                    commit.id()
+                );
+                side_tags.push((*oid, commit.id()));
+            }
+        }
+        Ok(side_tags)
+    }
+
+    /// Creates section for a release tagged off the traversed line, with commits since the previous tag on its branch,
+    /// or since its fork point.
+    fn traverse_side_release(
+        &mut self,
+        repo: &Repository,
+        tags: &HashMap<Oid, TagInfo>,
+        (tag_oid, fork_point): (Oid, Oid),
+        branch: &str,
+        interpreter: &CommitInterpreter,
+        patch_ids: &mut HashSet<Oid>,
+    ) -> Result<(), ChgError> {
+        let tag_commit = repo.find_commit(tag_oid)?;
+        self.release_section(&tags[&tag_oid], &tag_commit, branch);
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+        revwalk.push(tag_oid)?;
+        revwalk.hide(fork_point)?;
+        revwalk.simplify_first_parent()?;
+        for oid in revwalk {
+            let oid = oid?;
+            if oid != tag_oid && tags.contains_key(&oid) {
+                break;
+            }
+            let commit = repo.find_commit(oid)?;
+            if let Some(patch_id) = patch_id(repo, &commit)? {
+                patch_ids.insert(patch_id);
+            }
+            if oid == tag_oid {
+                continue;
+            }
+            if let Some((info, cm)) = interpreter.interpret(repo, &commit)? {
+                self.handle_untagged_commit(None, info, cm, interpreter)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts section for release tagged on the commit, with the annotated tag message as release notes.
+    /// Returns the version, or `None` if the tag is not a release tag.
+    ///
+    /// Release date is the tagger date of annotated tags, or the commit date.
+    /// Release is yanked if the tag name contains `YANKED`, or the tag message contains configured `yanked_marker`.
+    fn release_section(&mut self, tag: &TagInfo, commit: &Commit, branch: &str) -> Option<String> {
+        let version = self.tag_name_to_version(&tag.name)?;
+        let timestamp = tag
+            .date
+            .unwrap_or_else(|| git_time_to_chrono(commit.author().when()));
+        let marker = self.config().git.yanked_marker.clone();
+        let mut notes = tag.message.as_deref().unwrap_or("").trim().to_string();
+        let mut yanked = tag.name.to_uppercase().contains("YANKED");
+        if !marker.is_empty() && notes.contains(&marker) {
+            yanked = true;
+            notes = notes
+                .lines()
+                .map(|line| {
+                    if line.contains(&marker) {
+                        line.replace(&marker, "").trim().to_string()
+                    } else {
+                        line.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim()
+                .to_string();
+        }
+        let mut header = ReleaseHeader::release_tagged(&tag.name, &version, timestamp, yanked);
+        header.branch = branch.to_string();
+        self.section(Some(header));
+        // tools often put just the tag name into the message
+        if notes != tag.name && notes != version {
+            self.release_notes(&notes);
+        }
+        Some(version)
+    }
+
     /// Processes commit without a release tag.
     ///
     /// Returns false if processing further commits should stop (based on [`stop_version`])
-    fn handle_untagged_commit(&mut self, stop_version: Option<&str>, author: &str, ts: NaiveDate, cm: CommitMessage) -> bool {
+    fn handle_untagged_commit(
+        &mut self,
+        stop_version: Option<&str>,
+        info: CommitInfo,
+        cm: CommitMessage,
+        interpreter: &CommitInterpreter,
+    ) -> Result<bool, ChgError> {
+        let author = info.author.as_str();
+        let ts = info.timestamp;
         match cm {
             CommitMessage::Contribution {
                 component,
-                refs,
+                mut refs,
                 subject,
-                details: _,
+                details,
+                changelog_text,
+                change_type,
+                co_authors,
             } => {
+                for r in info.merged_refs {
+                    if !refs.contains(&r) {
+                        refs.push(r);
+                    }
+                }
+                // PR title, labels and author handle from the hosting service; issues only give labels
+                let (subject, labels, authors) = match interpreter.enricher.enrich(&refs) {
+                    Ok(Some(metadata)) if metadata.pull_request => {
+                        let authors = match metadata.author {
+                            None => vec![author.to_string()],
+                            Some(handle) => vec![handle],
+                        };
+                        (metadata.title, metadata.labels, authors)
+                    }
+                    Ok(Some(metadata)) => (subject, metadata.labels, vec![author.to_string()]),
+                    Ok(None) => (subject, vec![], vec![author.to_string()]),
+                    Err(e) => {
+                        warn!("Cannot enrich {:?}: {}", refs, e);
+                        (subject, vec![], vec![author.to_string()])
+                    }
+                };
+                let subject = changelog_text.unwrap_or(subject);
+                let mut authors = authors;
+                for author in co_authors.into_iter().chain(info.merged_authors) {
+                    if !authors.contains(&author) {
+                        authors.push(author);
+                    }
+                }
+                let classification = match change_type {
+                    // from a trailer
+                    Some(change_type) => Classification {
+                        change_type,
+                        confidence: Confidence::High,
+                    },
+                    None => interpreter.classifier.classify(&subject, &labels),
+                };
+                // component: from the message, then from changed paths, then from `[xyz]` subject prefix
+                let (component, subject) = if !component.is_empty() {
+                    (component, subject)
+                } else {
+                    let components = interpreter.components.resolve_paths(&info.paths);
+                    if !components.is_empty() {
+                        (components.join(","), subject)
+                    } else {
+                        match ComponentResolver::from_subject(&subject) {
+                            Some((component, rest))
+                                if !interpreter.classifier.is_type_tag(&component) =>
+                            {
+                                (component, rest)
+                            }
+                            _ => (component, subject),
+                        }
+                    }
+                };
+                if classification.confidence == Confidence::Low {
+                    debug!(
+                        "Change type not recognized ({:?}): '{}'",
+                        classification.change_type, subject
+                    );
+                }
                 self.item(ChangeItem {
                     refs,
-                    change_type: ChangeType::Other, // TODO
+                    change_type: classification.change_type,
+                    type_confidence: Some(classification.confidence),
                     component,
                     text: subject,
-                    authors: vec![author.to_string()],
-                })
-                    .unwrap(); // TODO
+                    authors,
+                    commit: Some(info.id.to_string()),
+                    details: if interpreter.details {
+                        details
+                    } else {
+                        String::new()
+                    },
+                })?;
             }
             CommitMessage::Release { version } => {
-                if let Some(rh) = ReleaseHeader::release(version.as_str(), ts, true)
-                {
+                if let Some(rh) = ReleaseHeader::release(version.as_str(), ts, true) {
                     warn!(
                         "Untagged release detected: {} - will be considered yenked",
                         version
@@ -126,15 +563,15 @@ impl ChangeLogBuilder {
                     self.section(Some(rh));
                     if let Some(stop_version) = stop_version {
                         if stop_version == version {
-                            trace!(
-                                "Stopping on version '{}' as requested",
-                                version
-                            );
-                            return false;
+                            trace!("Stopping on version '{}' as requested", version);
+                            return Ok(false);
                         }
                     }
                 }
             }
+            CommitMessage::Skipped { subject } => {
+                debug!("Skipped by trailer: {}", subject);
+            }
             CommitMessage::PostRelease { ref_ver } => {
                 debug!("Post-release detected, ignoring commit: {}", ref_ver);
             }
@@ -142,16 +579,11 @@ impl ChangeLogBuilder {
                 warn!("Revert detected but not implemented yet: '{}'", orig_msg);
             }
         }
-        return true;
+        Ok(true)
     }
 
     fn tag_name_to_version(&self, tag_name: &str) -> Option<String> {
-        if tag_name.starts_with("v") {
-            //TODO: configurable
-            Some(tag_name[1..].to_owned())
-        } else {
-            None
-        }
+        self.config().tag_to_version(tag_name)
     }
 }
 
@@ -160,15 +592,90 @@ impl ChangeLog {
         dir: D,
         stop_version: Option<String>,
         config: &ChangeLogConfig,
-    ) -> Self {
-        let repo = Repository::open(dir).unwrap();
-        let tags = list_tags(&repo).unwrap();
+    ) -> Result<Self, ChgError> {
+        let options = GitImportOptions {
+            stop_version,
+            ..Default::default()
+        };
+        Self::import_git(dir, &options, config)
+    }
+
+    /// Imports the git history, as specified by options.
+    ///
+    /// Releases tagged on maintenance branches get their own sections, ordered by version.
+    /// Commits cherry-picked to maintenance branches are only listed there.
+    pub fn import_git<D: AsRef<Path>>(
+        dir: D,
+        options: &GitImportOptions,
+        config: &ChangeLogConfig,
+    ) -> Result<Self, ChgError> {
+        let repo = Repository::open(dir)?;
+        let tags = list_tags(&repo)?;
+        let interpreter = CommitInterpreter::new(config, &repo, options)?;
         let mut builder = ChangeLogBuilder::new(config.clone());
         builder.section(None);
-        let stop_version = stop_version.as_ref().map(String::as_str);
-        builder
-            .traverse_commits(&repo, &tags, stop_version)
-            .unwrap();
-        builder.build()
+        let mut visited = HashSet::new();
+        builder.traverse_commits(&repo, &tags, options, &interpreter, &mut visited)?;
+
+        let side_tags = builder.side_release_tags(&repo, &tags, &visited)?;
+        let mut changelog = builder.build();
+        if side_tags.is_empty() {
+            return Ok(changelog);
+        }
+
+        let mut side_builder = ChangeLogBuilder::new(config.clone());
+        let mut patch_ids = HashSet::new();
+        for side_tag in side_tags {
+            let branch = branch_containing(&repo, side_tag.0, &visited)?;
+            side_builder.traverse_side_release(
+                &repo,
+                &tags,
+                side_tag,
+                &branch,
+                &interpreter,
+                &mut patch_ids,
+            )?;
+        }
+
+        // cherry-picks
+        let sections = changelog.unreleased.iter_mut().chain(
+            changelog
+                .releases
+                .iter_mut()
+                .map(|(_, changeset)| changeset),
+        );
+        for changeset in sections {
+            let mut items = Vec::new();
+            for item in changeset.items.drain(..) {
+                let commit = match &item.commit {
+                    None => None,
+                    Some(commit) => Some(repo.find_commit(Oid::from_str(commit)?)?),
+                };
+                match commit {
+                    Some(commit)
+                        if patch_id(&repo, &commit)?.is_some_and(|p| patch_ids.contains(&p)) =>
+                    {
+                        debug!(
+                            "'{}' is cherry-picked to a maintenance branch, listing it there",
+                            item.text
+                        );
+                    }
+                    _ => items.push(item),
+                }
+            }
+            changeset.items = items;
+        }
+
+        for (header, changeset) in side_builder.build().releases {
+            let pos = changelog
+                .releases
+                .iter()
+                .position(|(rh, _)| {
+                    compare_versions(&rh.version, &header.version) == Ordering::Less
+                })
+                .unwrap_or(changelog.releases.len());
+            changelog.releases.insert(pos, (header, changeset));
+        }
+        Ok(changelog)
     }
 }
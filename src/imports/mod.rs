@@ -1,3 +1,9 @@
+pub mod change_type;
 mod commit_msg;
+pub mod components;
+pub mod enrich;
 pub mod from_changelog;
+pub mod from_debian;
 pub mod from_git_repo;
+mod release_profile;
+pub mod trailers;
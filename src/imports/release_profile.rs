@@ -0,0 +1,178 @@
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::error::ChgError;
+use crate::ChangeLogConfig;
+
+/// Release commit conventions of a build tool; see `[git] release_profiles` config.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ReleaseProfile {
+    /// `Release 2.5.0`, and `Next devel cycle` after it
+    Default,
+    /// `[maven-release-plugin] prepare release foo-1.2.3`
+    Maven,
+    /// `chore: Release foo version 1.2.3`, or `(cargo-release) version 1.2.3` by older versions
+    Cargo,
+    /// `1.2.3`, as committed by `npm version`
+    Npm,
+    /// `chore(release): 1.2.3 [skip ci]`
+    SemanticRelease,
+}
+
+impl FromStr for ReleaseProfile {
+    type Err = ChgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" | "default" => Ok(ReleaseProfile::Default),
+            "maven" => Ok(ReleaseProfile::Maven),
+            "cargo" => Ok(ReleaseProfile::Cargo),
+            "npm" => Ok(ReleaseProfile::Npm),
+            "semantic-release" => Ok(ReleaseProfile::SemanticRelease),
+            _ => Err(ChgError::UnknownReleaseProfile(s.to_string())),
+        }
+    }
+}
+
+impl ReleaseProfile {
+    /// Patterns of release and post-release commit subjects, capturing the `version`.
+    fn patterns(&self) -> (&'static str, Option<&'static str>) {
+        match self {
+            ReleaseProfile::Default => (
+                r"(?i:RELEASE[SD]?)\s+[\D]*(?P<version>\d+[\\.\-][\\.\-\d]+)",
+                Some(r"(?i:NEXT DEVEL CYCLE)\s*[\D]*(?P<version>.*)"),
+            ),
+            ReleaseProfile::Maven => (
+                r"^\[maven-release-plugin\] prepare release (?:\S*-)?v?(?P<version>\d+(?:\.\d+)+\S*)$",
+                Some(r"^\[maven-release-plugin\] prepare for next development iteration"),
+            ),
+            ReleaseProfile::Cargo => (
+                r"^(?:chore: Release (?:\S+ )?version|\(cargo-release\) version) v?(?P<version>\d+\.\d+\.\d+\S*)$",
+                Some(r"^\(cargo-release\) start next development iteration (?P<version>\S+)$"),
+            ),
+            ReleaseProfile::Npm => (r"^v?(?P<version>\d+\.\d+\.\d+(?:-[\w.]+)?)$", None),
+            ReleaseProfile::SemanticRelease => (
+                r"^chore\(release\): v?(?P<version>\d+\.\d+\.\d+\S*)(?: \[skip ci\])?",
+                None,
+            ),
+        }
+    }
+}
+
+/// Release and post-release commit patterns: custom ones first, then those of configured profiles.
+pub(crate) struct ReleasePatterns {
+    pub(crate) release: Vec<Regex>,
+    pub(crate) postrelease: Vec<Regex>,
+}
+
+impl ReleasePatterns {
+    pub(crate) fn from_config(config: &ChangeLogConfig) -> Result<Self, ChgError> {
+        let git = &config.git;
+        let mut patterns = Self {
+            release: compile(&git.release_patterns, true)?,
+            postrelease: compile(&git.postrelease_patterns, false)?,
+        };
+        let profiles = if git.release_profiles.is_empty() {
+            vec![ReleaseProfile::Default]
+        } else {
+            git.release_profiles
+                .iter()
+                .map(|p| ReleaseProfile::from_str(p))
+                .collect::<Result<_, _>>()?
+        };
+        for profile in profiles {
+            patterns.add_profile(profile);
+        }
+        Ok(patterns)
+    }
+
+    fn add_profile(&mut self, profile: ReleaseProfile) {
+        let (release, postrelease) = profile.patterns();
+        self.release.push(Regex::new(release).unwrap());
+        if let Some(postrelease) = postrelease {
+            self.postrelease.push(Regex::new(postrelease).unwrap());
+        }
+    }
+}
+
+impl Default for ReleasePatterns {
+    fn default() -> Self {
+        let mut patterns = Self {
+            release: vec![],
+            postrelease: vec![],
+        };
+        patterns.add_profile(ReleaseProfile::Default);
+        patterns
+    }
+}
+
+/// Release patterns need to capture the `version`; for post-release ones it is optional.
+fn compile(patterns: &[String], version_required: bool) -> Result<Vec<Regex>, ChgError> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            let invalid =
+                |message: String| ChgError::InvalidReleasePattern(pattern.clone(), message);
+            let regex = Regex::new(pattern).map_err(|e| invalid(e.to_string()))?;
+            if version_required && !regex.capture_names().any(|n| n == Some("version")) {
+                return Err(invalid("missing group (?P<version>...)".to_string()));
+            }
+            Ok(regex)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::imports::release_profile::ReleasePatterns;
+    use crate::ChangeLogConfig;
+
+    fn release_version(patterns: &ReleasePatterns, subject: &str) -> Option<String> {
+        patterns
+            .release
+            .iter()
+            .find_map(|r| r.captures(subject))
+            .map(|c| c["version"].to_string())
+    }
+
+    #[test]
+    fn profiles() {
+        let config = ChangeLogConfig::parse_embedded(
+            r#"<!-- CHANGELOG-CONFIG
+[git]
+release_profiles = ["maven", "cargo", "npm", "semantic-release"]
+release_patterns = ['^\[BUILD\] Released (?P<version>\S+)$']
+-->"#,
+        )
+        .unwrap();
+        let patterns = ReleasePatterns::from_config(&config).unwrap();
+        let version = |subject| release_version(&patterns, subject);
+        assert_eq!(
+            version("[maven-release-plugin] prepare release foo-bar-1.2.3").unwrap(),
+            "1.2.3"
+        );
+        assert_eq!(
+            version("chore: Release foo version 1.2.3").unwrap(),
+            "1.2.3"
+        );
+        assert_eq!(
+            version("(cargo-release) version 0.4.0-rc.1").unwrap(),
+            "0.4.0-rc.1"
+        );
+        assert_eq!(version("1.2.3").unwrap(), "1.2.3");
+        assert_eq!(version("chore(release): 1.2.3 [skip ci]").unwrap(), "1.2.3");
+        assert_eq!(version("[BUILD] Released 7.0").unwrap(), "7.0");
+        assert!(version("Release 2.5.0").is_none());
+        assert!(patterns
+            .postrelease
+            .iter()
+            .any(|r| r.is_match("[maven-release-plugin] prepare for next development iteration")));
+
+        let config = ChangeLogConfig::parse_embedded(
+            "<!-- CHANGELOG-CONFIG\n[git]\nrelease_patterns = [\"^Version \\\\d+\"]\n-->",
+        )
+        .unwrap();
+        assert!(ReleasePatterns::from_config(&config).is_err());
+    }
+}
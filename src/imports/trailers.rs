@@ -0,0 +1,203 @@
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::error::ChgError;
+
+/// Trailers added by git itself; a trailer block containing one of them may have other lines too.
+const GIT_GENERATED_PREFIXES: [&str; 2] = ["Signed-off-by: ", "(cherry picked from commit "];
+
+/// Commit message split into subject, body and trailers, like `git interpret-trailers --parse` does.
+#[derive(Debug, Default, Clone)]
+pub struct Message {
+    /// first line
+    pub subject: String,
+    /// lines between subject and trailers, with surrounding blank lines removed
+    pub body: String,
+    pub trailers: Vec<Trailer>,
+}
+
+/// Trailer like `Co-authored-by: Qiang Kou <kou@example.com>`; continuation lines are joined to the value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trailer {
+    pub key: String,
+    pub value: String,
+}
+
+impl Trailer {
+    /// Keys are case-insensitive, and `BREAKING CHANGE` is the same as `BREAKING-CHANGE`.
+    pub fn is(&self, key: &str) -> bool {
+        normalize_key(&self.key) == normalize_key(key)
+    }
+}
+
+pub(crate) fn normalize_key(key: &str) -> String {
+    key.trim().replace(' ', "-").to_ascii_lowercase()
+}
+
+impl Message {
+    pub fn parse(msg: &str) -> Message {
+        let trailer_regex =
+            Regex::new(r"^(?P<key>[A-Za-z0-9][A-Za-z0-9\-]*|BREAKING CHANGE)\s*:\s*(?P<value>.*)$")
+                .unwrap();
+        let mut lines: Vec<&str> = msg.lines().map(str::trim_end).collect();
+        // patch part, as in `git format-patch` output
+        if let Some(divider) = lines
+            .iter()
+            .position(|line| *line == "---" || line.starts_with("--- "))
+        {
+            lines.truncate(divider);
+        }
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        let first = match lines.iter().position(|line| !line.is_empty()) {
+            None => return Message::default(),
+            Some(first) => first,
+        };
+        let subject = lines[first].trim().to_string();
+        let lines = &lines[first + 1..];
+
+        // trailers are the last paragraph, never the subject
+        let start = lines
+            .iter()
+            .rposition(|line| line.is_empty())
+            .map_or(0, |i| i + 1);
+        let block = &lines[start..];
+        let mut trailers: Vec<Trailer> = vec![];
+        let mut other_lines = 0;
+        for line in block {
+            if line.starts_with(char::is_whitespace) && !trailers.is_empty() {
+                let last = trailers.last_mut().unwrap();
+                last.value.push(' ');
+                last.value.push_str(line.trim());
+            } else if let Some(captures) = trailer_regex.captures(line) {
+                trailers.push(Trailer {
+                    key: captures["key"].to_string(),
+                    value: captures["value"].trim().to_string(),
+                });
+            } else {
+                other_lines += 1;
+            }
+        }
+        let git_generated = block
+            .iter()
+            .any(|line| GIT_GENERATED_PREFIXES.iter().any(|p| line.starts_with(p)));
+        let is_block = !trailers.is_empty()
+            && (other_lines == 0 || (git_generated && trailers.len() * 3 >= other_lines));
+        let body_end = if is_block {
+            start
+        } else {
+            trailers.clear();
+            lines.len()
+        };
+        let body = lines[..body_end].join("\n").trim().to_string();
+        Message {
+            subject,
+            body,
+            trailers,
+        }
+    }
+
+    /// Value of the first trailer with given key.
+    pub fn trailer(&self, key: &str) -> Option<&str> {
+        self.trailers
+            .iter()
+            .find(|t| t.is(key))
+            .map(|t| t.value.as_str())
+    }
+}
+
+/// Item field that a trailer value goes to; see `[trailers]` config.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TrailerField {
+    /// item text instead of commit subject; `skip` leaves the commit out
+    Text,
+    /// appended to item details
+    Details,
+    /// change type, like `fixed`
+    Type,
+    Component,
+    /// comma separated refs, like `#12, PR#14`
+    Refs,
+    /// `Name <email>`, only name is used
+    Authors,
+    /// any value other than `false` or `no` leaves the commit out
+    Skip,
+    /// trailer is not used
+    None,
+}
+
+impl FromStr for TrailerField {
+    type Err = ChgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "text" => Ok(TrailerField::Text),
+            "details" => Ok(TrailerField::Details),
+            "type" => Ok(TrailerField::Type),
+            "component" => Ok(TrailerField::Component),
+            "refs" => Ok(TrailerField::Refs),
+            "authors" => Ok(TrailerField::Authors),
+            "skip" => Ok(TrailerField::Skip),
+            "" | "none" => Ok(TrailerField::None),
+            _ => Err(ChgError::UnknownTrailerField(s.to_string())),
+        }
+    }
+}
+
+/// Trailers recognized without any config.
+pub(crate) const DEFAULT_TRAILER_FIELDS: [(&str, TrailerField); 6] = [
+    ("Changelog", TrailerField::Text),
+    ("Changelog-Type", TrailerField::Type),
+    ("Component", TrailerField::Component),
+    ("Refs", TrailerField::Refs),
+    ("Co-authored-by", TrailerField::Authors),
+    ("BREAKING-CHANGE", TrailerField::Details),
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::imports::trailers::{Message, Trailer};
+
+    fn trailer(key: &str, value: &str) -> Trailer {
+        Trailer {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn interpret_trailers() {
+        let msg = Message::parse(
+            "Fix parser\n\nLonger description.\nRefs: not a trailer, in the body\n\nRefs: #12\nCo-authored-by: Qiang Kou\n  <kou@example.com>\nBREAKING CHANGE: input must be UTF-8\n",
+        );
+        assert_eq!(msg.subject, "Fix parser");
+        assert_eq!(
+            msg.body,
+            "Longer description.\nRefs: not a trailer, in the body"
+        );
+        assert_eq!(
+            msg.trailers,
+            vec![
+                trailer("Refs", "#12"),
+                trailer("Co-authored-by", "Qiang Kou <kou@example.com>"),
+                trailer("BREAKING CHANGE", "input must be UTF-8"),
+            ]
+        );
+        assert_eq!(msg.trailer("breaking-change"), Some("input must be UTF-8"));
+
+        // subject is never a trailer; other lines need a git-generated trailer
+        let msg = Message::parse("Changelog: skip");
+        assert!(msg.trailers.is_empty());
+        let msg = Message::parse("Fix\n\nSome text\nChangelog: skip");
+        assert!(msg.trailers.is_empty());
+        assert_eq!(msg.body, "Some text\nChangelog: skip");
+        let msg = Message::parse(
+            "Fix\n\nChangelog: skip\nReviewed by the team\nSigned-off-by: PK <pk@example.com>\n---\n src/lib.rs | 2 +-",
+        );
+        assert_eq!(msg.trailer("changelog"), Some("skip"));
+        assert_eq!(msg.trailers.len(), 2);
+        assert!(msg.body.is_empty());
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+use regex::Regex;
+
+use crate::api::{ChangeItem, ChangeType, Reference, ReleaseHeader};
+use crate::error::ChgError;
+use crate::template::Template;
+use crate::ChangeLogConfig;
+
+pub(crate) const DEFAULT_HEADING: &str =
+    "## {version} - {date}{#branch} ({branch}){/branch}{#yanked} [{yanked}]{/yanked}";
+/// Source commits are left out, so that existing changelogs do not change; custom templates can add `{commit}`.
+pub(crate) const DEFAULT_ITEM: &str =
+    "- {#refs}{refs}: {/refs}{#component}[{component}] {/component}{text} / {authors}";
+
+const HEADING_NAMES: &[&str] = &["version", "date", "branch", "yanked"];
+const ITEM_NAMES: &[&str] = &[
+    "refs",
+    "ref_links",
+    "component",
+    "text",
+    "authors",
+    "commit",
+    "type",
+];
+const TYPE_HEADING_NAMES: &[&str] = &["type"];
+const FOOTER_NAMES: &[&str] = &["version", "previous"];
+
+/// Markdown layout per `[templates]` config, used both for rendering and for parsing.
+pub(crate) struct MarkdownLayout {
+    heading: Template,
+    item: Template,
+    type_heading: Option<Template>,
+    footer: Option<Template>,
+    /// parsers for custom templates; the built-in layout has its own, more forgiving parsers
+    heading_regex: Option<Regex>,
+    item_regex: Option<Regex>,
+    type_heading_regex: Option<Regex>,
+    footer_regex: Option<Regex>,
+}
+
+impl MarkdownLayout {
+    pub(crate) fn from_config(config: &ChangeLogConfig) -> Result<Self, ChgError> {
+        let templates = &config.templates;
+        let optional = |text: &str, names: &[&str]| -> Result<Option<Template>, ChgError> {
+            if text.is_empty() {
+                Ok(None)
+            } else {
+                Template::parse(text, names).map(Some)
+            }
+        };
+        let custom_heading = optional(&templates.heading, HEADING_NAMES)?;
+        let custom_item = optional(&templates.item, ITEM_NAMES)?;
+        let type_heading = optional(&templates.type_heading, TYPE_HEADING_NAMES)?;
+        let footer = optional(&templates.footer, FOOTER_NAMES)?;
+        let mut patterns = HashMap::new();
+        patterns.insert("date", r"\d{4}-\d{2}-\d{2}");
+        patterns.insert("version", r"\d\S*?");
+        patterns.insert("branch", r"\S+?");
+        patterns.insert(
+            "commit",
+            r"<!--\s*[0-9a-f]{7,40}\s*-->|\(\[[0-9a-f]{7,40}]\(\S*\)\)|\([0-9a-f]{7,40}\)",
+        );
+        Ok(Self {
+            heading_regex: custom_heading
+                .as_ref()
+                .map(|t| t.regex(&patterns))
+                .transpose()?,
+            item_regex: custom_item
+                .as_ref()
+                .map(|t| t.regex(&patterns))
+                .transpose()?,
+            type_heading_regex: type_heading
+                .as_ref()
+                .map(|t| t.regex(&patterns))
+                .transpose()?,
+            footer_regex: footer.as_ref().map(|t| t.regex(&patterns)).transpose()?,
+            heading: match custom_heading {
+                None => Template::parse(DEFAULT_HEADING, HEADING_NAMES)?,
+                Some(heading) => heading,
+            },
+            item: match custom_item {
+                None => Template::parse(DEFAULT_ITEM, ITEM_NAMES)?,
+                Some(item) => item,
+            },
+            type_heading,
+            footer,
+        })
+    }
+
+    pub(crate) fn is_grouped(&self) -> bool {
+        self.type_heading.is_some()
+    }
+
+    /// True if items keep their source commit in the markdown, so that it can be matched on the next sync.
+    pub(crate) fn renders_commit(&self) -> bool {
+        self.item.has_value("commit")
+    }
+
+    pub(crate) fn render_heading(&self, header: &ReleaseHeader) -> String {
+        let mut values = HashMap::new();
+        values.insert("version", header.version.clone());
+        values.insert("date", header.timestamp.to_string());
+        values.insert("branch", header.branch.clone());
+        values.insert(
+            "yanked",
+            if header.yanked { "YANKED" } else { "" }.to_string(),
+        );
+        self.heading.render(&values)
+    }
+
+    pub(crate) fn render_item(&self, item: &ChangeItem, config: &ChangeLogConfig) -> String {
+        let mut values = HashMap::new();
+        values.insert("refs", Reference::join(&item.refs));
+        let ref_links: Vec<String> = item
+            .refs
+            .iter()
+            .map(|reference| match config.ref_link(reference) {
+                None => reference.to_string(),
+                Some(link) => format!("[{}]({})", reference, link),
+            })
+            .collect();
+        values.insert("ref_links", ref_links.join(", "));
+        values.insert("component", item.component.clone());
+        values.insert("text", item.text.clone());
+        values.insert("authors", item.authors.join(", "));
+        // source commit: linked if possible, otherwise just kept for later syncs
+        let commit = match (&item.commit, item.short_commit()) {
+            (Some(commit), Some(short)) => match config.commit_link(commit) {
+                None => format!("<!-- {} -->", short),
+                Some(link) => format!("([{}]({}))", short, link),
+            },
+            _ => "".to_string(),
+        };
+        values.insert("commit", commit);
+        values.insert("type", type_value(item.change_type));
+        let mut text = self.item.render(&values);
+        for line in item.details.lines() {
+            text.push_str("\n  ");
+            text.push_str(line);
+        }
+        text
+    }
+
+    pub(crate) fn render_type_heading(&self, change_type: ChangeType) -> Option<String> {
+        let mut values = HashMap::new();
+        values.insert("type", change_type.title().to_string());
+        self.type_heading.as_ref().map(|t| t.render(&values))
+    }
+
+    /// Footer lines, one per release, like link definitions for comparing with the previous release.
+    pub(crate) fn render_footer(
+        &self,
+        releases: &[(ReleaseHeader, crate::api::ChangeSet)],
+    ) -> Vec<String> {
+        let footer = match &self.footer {
+            None => return vec![],
+            Some(footer) => footer,
+        };
+        releases
+            .iter()
+            .enumerate()
+            .map(|(i, (header, _))| {
+                let mut values = HashMap::new();
+                values.insert("version", header.version.clone());
+                let previous = releases.get(i + 1).map(|(h, _)| h.version.clone());
+                values.insert("previous", previous.unwrap_or_default());
+                footer.render(&values)
+            })
+            .collect()
+    }
+
+    /// Parses release heading per custom template; `None` when the layout has no custom heading, or it does not match.
+    pub(crate) fn parse_heading(&self, line: &str) -> Result<Option<ReleaseHeader>, ChgError> {
+        let captures = match self.heading_regex.as_ref().and_then(|r| r.captures(line)) {
+            None => return Ok(None),
+            Some(captures) => captures,
+        };
+        let version = captures.name("version").map_or("", |m| m.as_str());
+        let date = captures.name("date").map_or("", |m| m.as_str());
+        let timestamp = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| ChgError::InvalidTimestamp(line.to_string(), e.to_string()))?;
+        let yanked = captures
+            .name("yanked")
+            .is_some_and(|m| !m.as_str().is_empty());
+        let mut header = ReleaseHeader::release_tagged("", version, timestamp, yanked);
+        header.branch = captures
+            .name("branch")
+            .map_or("", |m| m.as_str())
+            .to_string();
+        Ok(Some(header))
+    }
+
+    pub(crate) fn has_custom_item(&self) -> bool {
+        self.item_regex.is_some()
+    }
+
+    /// Parses item per custom template.
+    pub(crate) fn parse_item(&self, line: &str) -> Option<ChangeItem> {
+        let captures = self.item_regex.as_ref()?.captures(line)?;
+        let value = |name: &str| captures.name(name).map_or("", |m| m.as_str().trim());
+        let list = |text: &str| -> Vec<String> {
+            text.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        };
+        static LINK: OnceLock<Regex> = OnceLock::new();
+        static COMMIT: OnceLock<Regex> = OnceLock::new();
+        let link_regex = LINK.get_or_init(|| Regex::new(r"^\[(?P<text>[^\]]*)]\(.*\)$").unwrap());
+        let mut refs = list(value("refs"));
+        if refs.is_empty() {
+            refs = list(value("ref_links"))
+                .into_iter()
+                .map(|r| match link_regex.captures(&r) {
+                    None => r,
+                    Some(c) => c["text"].to_string(),
+                })
+                .collect();
+        }
+        let refs = refs.iter().map(|r| Reference::parse(r)).collect();
+        let commit_regex = COMMIT.get_or_init(|| Regex::new("[0-9a-f]{7,40}").unwrap());
+        Some(ChangeItem {
+            refs,
+            change_type: ChangeType::from_str(value("type")).unwrap_or(ChangeType::Other),
+            component: value("component").to_string(),
+            text: value("text").to_string(),
+            authors: list(value("authors")),
+            commit: commit_regex
+                .find(value("commit"))
+                .map(|m| m.as_str().to_string()),
+            details: String::new(),
+            type_confidence: None,
+        })
+    }
+
+    /// Parses heading of a change type group.
+    pub(crate) fn parse_type_heading(&self, line: &str) -> Option<ChangeType> {
+        let captures = self.type_heading_regex.as_ref()?.captures(line)?;
+        ChangeType::from_str(captures.name("type")?.as_str()).ok()
+    }
+
+    pub(crate) fn is_footer(&self, line: &str) -> bool {
+        self.footer_regex.as_ref().is_some_and(|r| r.is_match(line))
+    }
+}
+
+/// `Other` is rendered as empty, so that it can be left out by a template section.
+fn type_value(change_type: ChangeType) -> String {
+    if change_type == ChangeType::Other {
+        "".to_string()
+    } else {
+        change_type.title().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::ChangeType;
+    use crate::ChangeLog;
+
+    const CHANGELOG: &str = r####"# Changelog
+<!-- CHANGELOG-CONFIG
+[keys]
+pr_link = "https://github.com/o/r/pull/{number}"
+[templates]
+heading = "## [{version}] - {date}{#yanked} *{yanked}*{/yanked}"
+item = "- {#component}**{component}**: {/component}{text}{#refs} ({ref_links}){/refs}{#authors} — {authors}{/authors}"
+type_heading = "### {type}"
+footer = "[{version}]: https://github.com/o/r/{#previous}compare/v{previous}...v{version}{/previous}"
+-->
+
+## Unreleased
+
+### Fixed
+
+- **core**: Fix crash ([PR#12](https://github.com/o/r/pull/12), #3) — @pk
+
+## [1.1.0] - 2020-10-01 *YANKED*
+
+### Added
+
+- Add parser — @pk, @jd
+
+### Other
+
+- Update docs
+
+## [1.0.0] - 2020-09-15
+
+[1.1.0]: https://github.com/o/r/compare/v1.0.0...v1.1.0
+[1.0.0]: https://github.com/o/r/
+
+Epilog.
+"####;
+
+    #[test]
+    fn custom_templates_roundtrip() {
+        let changelog = ChangeLog::import_markdown(CHANGELOG).unwrap();
+        let item = &changelog.unreleased.as_ref().unwrap().items[0];
+        assert_eq!(item.refs, vec!["PR#12", "#3"]);
+        assert_eq!(item.component, "core");
+        assert_eq!(item.text, "Fix crash");
+        assert_eq!(item.authors, vec!["@pk"]);
+        assert_eq!(item.change_type, ChangeType::Fixed);
+        let (header, changeset) = &changelog.releases[0];
+        assert_eq!(header.version, "1.1.0");
+        assert!(header.yanked);
+        assert_eq!(changeset.items[0].authors, vec!["@pk", "@jd"]);
+        assert_eq!(changeset.items[1].text, "Update docs");
+        assert!(changeset.items[1].authors.is_empty());
+        assert_eq!(changelog.epilog.trim(), "Epilog.");
+
+        let mut out = Vec::new();
+        changelog.print_markdown(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), CHANGELOG);
+    }
+
+    #[test]
+    fn commit_only_with_template() {
+        let markdown = |changelog: &ChangeLog| {
+            let mut out = Vec::new();
+            changelog.print_markdown(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let changelog = ChangeLog::import_markdown(
+            "# Changelog\n\n## Unreleased\n\n- Fix NPE / pk <!-- 1a2b3c4 -->\n",
+        )
+        .unwrap();
+        let item = &changelog.unreleased.as_ref().unwrap().items[0];
+        assert_eq!(item.commit.as_deref(), Some("1a2b3c4"));
+        assert!(markdown(&changelog).contains("- Fix NPE / pk\n"));
+
+        let text = "# Changelog\n<!-- CHANGELOG-CONFIG\n[templates]\nitem = \"- {text} / {authors}{#commit} {commit}{/commit}\"\n-->\n\n## Unreleased\n\n- Fix NPE / pk <!-- 1a2b3c4 -->\n";
+        let changelog = ChangeLog::import_markdown(text).unwrap();
+        assert!(markdown(&changelog).contains("- Fix NPE / pk <!-- 1a2b3c4 -->\n"));
+    }
+}
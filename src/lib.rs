@@ -8,8 +8,18 @@ pub use config::ChangeLogConfig;
 pub use error::ChgError;
 
 pub mod api;
+pub mod bootstrap;
 pub mod builder;
 mod changelog;
 mod config;
+pub mod discovery;
 mod error;
+pub mod exports;
+mod hosting;
 pub mod imports;
+mod layout;
+pub mod lint;
+mod reference;
+mod template;
+#[cfg(test)]
+mod test_util;
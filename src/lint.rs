@@ -0,0 +1,116 @@
+use std::fmt;
+
+use crate::api::{ChangeSet, Reference};
+use crate::error::ChgError;
+use crate::ChangeLog;
+
+/// Item that breaks a rule of [`ChangeLog::lint`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    /// release version, `None` for Unreleased
+    pub version: Option<String>,
+    pub text: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: '{}': {}",
+            self.version.as_deref().unwrap_or("Unreleased"),
+            self.text,
+            self.message
+        )
+    }
+}
+
+impl ChangeLog {
+    /// Checks items of the Unreleased section, or of all sections with `all`, per `[lint]` config.
+    pub fn lint(&self, all: bool) -> Result<Vec<LintIssue>, ChgError> {
+        let config = &self.config;
+        let require_tracker_key = config.lint.require_tracker_key;
+        if require_tracker_key && config.keys.trackers.is_empty() {
+            return Err(ChgError::MissingConfigValue("keys.trackers".to_string()));
+        }
+        let mut sections: Vec<(Option<&str>, &ChangeSet)> = vec![];
+        if let Some(unreleased) = &self.unreleased {
+            sections.push((None, unreleased));
+        }
+        if all {
+            sections.extend(
+                self.releases
+                    .iter()
+                    .map(|(header, changeset)| (Some(header.version.as_str()), changeset)),
+            );
+        }
+        let mut issues = vec![];
+        for (version, changeset) in sections {
+            for item in &changeset.items {
+                let mut issue = |message: String| {
+                    issues.push(LintIssue {
+                        version: version.map(str::to_string),
+                        text: item.text.clone(),
+                        message,
+                    })
+                };
+                if item.text.trim().is_empty() {
+                    issue("missing text".to_string());
+                }
+                let is_tracker_key = |r: &Reference| match r {
+                    Reference::ExternalTracker(key) => config.tracker_of(key).is_some(),
+                    _ => false,
+                };
+                if require_tracker_key && !item.refs.iter().any(is_tracker_key) {
+                    let names: Vec<&str> =
+                        config.keys.trackers.keys().map(String::as_str).collect();
+                    issue(format!("missing key of tracker {}", names.join(" or ")));
+                }
+            }
+        }
+        Ok(issues)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::Reference;
+    use crate::ChangeLog;
+
+    #[test]
+    fn require_tracker_key() {
+        let changelog = ChangeLog::import_markdown(
+            r#"# Changelog
+<!-- CHANGELOG-CONFIG
+[keys.trackers.jira]
+key = "PROJ-\\d+"
+link = "https://jira.example.com/browse/{key}"
+[lint]
+require_tracker_key = true
+-->
+
+## Unreleased
+- PROJ-12, #3: Fix crash / pk
+- #4: Add parser / pk
+
+## 1.0.0 - 2020-09-15
+- Initial version / pk
+"#,
+        )
+        .unwrap();
+        let issues = changelog.lint(false).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].to_string(),
+            "Unreleased: 'Add parser': missing key of tracker jira"
+        );
+        assert_eq!(changelog.lint(true).unwrap().len(), 2);
+        assert_eq!(
+            changelog
+                .config
+                .ref_link(&Reference::parse("PROJ-12"))
+                .unwrap(),
+            "https://jira.example.com/browse/PROJ-12"
+        );
+    }
+}
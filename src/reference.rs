@@ -0,0 +1,261 @@
+use std::fmt;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::api::same_commit;
+use crate::ChangeLogConfig;
+
+/// What a change item refers to, like an issue or a pull request.
+///
+/// References are compared by identity: `!12` is the same pull request as `PR#12`,
+/// and links recognized by [`ChangeLogConfig::canonical`] become what they link to.
+#[derive(Debug, Clone)]
+pub enum Reference {
+    /// `#12`, or `group/project#12` in another project
+    Issue { project: String, number: u64 },
+    /// `PR#12`, GitLab merge request `!12`, or `group/project!12` in another project
+    PullRequest {
+        project: String,
+        number: u64,
+        merge_request: bool,
+    },
+    /// commit id, possibly abbreviated
+    Commit(String),
+    /// key of an issue tracker from `[keys.trackers]`, like `PROJ-12`
+    ExternalTracker(String),
+    /// any other link
+    Url(String),
+}
+
+impl Reference {
+    /// Recognizes the reference by its form only; see [`ChangeLogConfig::canonical`] for links.
+    pub fn parse(text: &str) -> Reference {
+        let text = text.trim();
+        static LINK: OnceLock<Regex> = OnceLock::new();
+        static ISSUE_OR_PR: OnceLock<Regex> = OnceLock::new();
+        static COMMIT: OnceLock<Regex> = OnceLock::new();
+        let link_regex = LINK.get_or_init(|| Regex::new(r"^\[(?P<text>[^\]]+)]\(\S*\)$").unwrap());
+        if let Some(captures) = link_regex.captures(text) {
+            return Reference::parse(&captures["text"]);
+        }
+        let regex = ISSUE_OR_PR.get_or_init(|| {
+            Regex::new(r"^(?P<project>[\w.\-]+(?:/[\w.\-]+)+)?(?P<kind>PR#|#|!)(?P<number>\d+)$")
+                .unwrap()
+        });
+        if let Some(captures) = regex.captures(text) {
+            if let Ok(number) = captures["number"].parse() {
+                let project = captures
+                    .name("project")
+                    .map_or("", |m| m.as_str())
+                    .to_string();
+                return match &captures["kind"] {
+                    "#" => Reference::Issue { project, number },
+                    kind => Reference::PullRequest {
+                        project,
+                        number,
+                        merge_request: kind == "!",
+                    },
+                };
+            }
+        }
+        if text.starts_with("http://") || text.starts_with("https://") {
+            return Reference::Url(text.to_string());
+        }
+        let commit_regex = COMMIT.get_or_init(|| Regex::new("^[0-9a-f]{7,40}$").unwrap());
+        if commit_regex.is_match(text) {
+            return Reference::Commit(text.to_string());
+        }
+        Reference::ExternalTracker(text.to_string())
+    }
+
+    /// Normalized text, equal for the same issue, pull request etc.
+    fn identity(&self) -> String {
+        match self {
+            Reference::Issue { project, number } => format!("{}#{}", project, number),
+            Reference::PullRequest {
+                project, number, ..
+            } => format!("{}PR#{}", project, number),
+            Reference::Commit(id) => id.to_lowercase(),
+            Reference::ExternalTracker(key) => key.to_uppercase(),
+            Reference::Url(url) => url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// References as text, like `PR#12, #3`.
+    pub fn join(refs: &[Reference]) -> String {
+        let refs: Vec<String> = refs.iter().map(Reference::to_string).collect();
+        refs.join(", ")
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reference::Issue { project, number } => write!(f, "{}#{}", project, number),
+            Reference::PullRequest {
+                project,
+                number,
+                merge_request,
+            } => {
+                if *merge_request || !project.is_empty() {
+                    write!(f, "{}!{}", project, number)
+                } else {
+                    write!(f, "PR#{}", number)
+                }
+            }
+            Reference::Commit(id) => write!(f, "{}", id),
+            Reference::ExternalTracker(key) => write!(f, "{}", key),
+            Reference::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+impl PartialEq for Reference {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Reference::Commit(a), Reference::Commit(b)) => same_commit(a, b),
+            _ => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+                    && self.identity() == other.identity()
+            }
+        }
+    }
+}
+
+impl PartialEq<str> for Reference {
+    fn eq(&self, other: &str) -> bool {
+        *self == Reference::parse(other)
+    }
+}
+
+impl PartialEq<&str> for Reference {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl ChangeLogConfig {
+    /// Reference parsed from text, with links per `[keys]` turned into what they link to.
+    pub fn parse_reference(&self, text: &str) -> Reference {
+        self.canonical(Reference::parse(text))
+    }
+
+    /// Turns links matching `[keys]` templates, like `https://github.com/o/r/issues/12`, into issues, PRs etc.
+    pub fn canonical(&self, reference: Reference) -> Reference {
+        let url = match &reference {
+            Reference::Url(url) => url.trim_end_matches('/'),
+            _ => return reference,
+        };
+        let keys = &self.keys;
+        let templates = [
+            (&keys.issue_link, false, false),
+            (&keys.pr_link, true, false),
+            (&keys.project_issue_link, false, true),
+            (&keys.project_pr_link, true, true),
+        ];
+        for (template, pr, with_project) in templates.iter() {
+            let captures = match self.link_regex(template).and_then(|r| r.captures(url)) {
+                None => continue,
+                Some(captures) => captures,
+            };
+            let number = match captures
+                .name("number")
+                .and_then(|m| m.as_str().parse().ok())
+            {
+                None => continue,
+                Some(number) => number,
+            };
+            let project = match captures.name("project") {
+                Some(project) if *with_project => project.as_str().to_string(),
+                _ => "".to_string(),
+            };
+            return if *pr {
+                Reference::PullRequest {
+                    project,
+                    number,
+                    // GitLab links say what they are
+                    merge_request: template.contains("merge_requests"),
+                }
+            } else {
+                Reference::Issue { project, number }
+            };
+        }
+        if let Some(captures) = self
+            .link_regex(&keys.commit_link)
+            .and_then(|r| r.captures(url))
+        {
+            if let Some(commit) = captures.name("commit") {
+                return Reference::Commit(commit.as_str().to_string());
+            }
+        }
+        for tracker in keys.trackers.values() {
+            if let Some(captures) = self.link_regex(&tracker.link).and_then(|r| r.captures(url)) {
+                if let Some(key) = captures.name("key") {
+                    return Reference::ExternalTracker(key.as_str().to_string());
+                }
+            }
+        }
+        reference
+    }
+
+    /// Regex matching links made from the template, with its placeholders as named groups.
+    fn link_regex(&self, template: &str) -> Option<Regex> {
+        if template.is_empty() {
+            return None;
+        }
+        let regex = regex::escape(template.trim_end_matches('/'))
+            .replace(r"\{number\}", r"(?P<number>\d+)")
+            .replace(r"\{project\}", r"(?P<project>.+?)")
+            .replace(r"\{commit\}", r"(?P<commit>[0-9a-f]{7,40})")
+            .replace(r"\{key\}", r"(?P<key>[^/?#]+)");
+        self.regexes.get(format!("^{}$", regex))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::Reference;
+    use crate::ChangeLogConfig;
+
+    #[test]
+    fn canonical_identity() {
+        assert_eq!(Reference::parse("!12"), Reference::parse("PR#12"));
+        assert_ne!(Reference::parse("#12"), Reference::parse("PR#12"));
+        assert_ne!(Reference::parse("#12"), Reference::parse("g/r#12"));
+        assert_eq!(Reference::parse("abc1234"), Reference::parse("abc1234def"));
+        assert_eq!(Reference::parse("g/r!5").to_string(), "g/r!5");
+        assert_eq!(Reference::parse("PROJ-7"), "proj-7");
+        assert_eq!(
+            Reference::parse("[#5](https://github.com/o/r/issues/5)"),
+            "#5"
+        );
+
+        let config = ChangeLogConfig::parse_embedded(
+            r#"<!-- CHANGELOG-CONFIG
+[keys]
+issue_link = "https://github.com/o/r/issues/{number}"
+pr_link = "https://github.com/o/r/pull/{number}"
+project_issue_link = "https://github.com/{project}/issues/{number}"
+commit_link = "https://github.com/o/r/commit/{commit}"
+[keys.trackers.jira]
+key = "PROJ-\\d+"
+link = "https://jira.example.com/browse/{key}"
+-->"#,
+        )
+        .unwrap();
+        let parse = |text: &str| config.parse_reference(text);
+        assert_eq!(parse("https://github.com/o/r/issues/977"), "#977");
+        assert_eq!(parse("https://github.com/o/r/pull/979/"), "PR#979");
+        assert_eq!(parse("https://github.com/x/y/issues/3"), "x/y#3");
+        assert_eq!(
+            parse("https://github.com/o/r/commit/0123456789abcdef"),
+            Reference::Commit("0123456".to_string())
+        );
+        assert_eq!(parse("https://jira.example.com/browse/PROJ-1"), "PROJ-1");
+        assert_eq!(
+            parse("https://example.com/x"),
+            Reference::Url("https://example.com/x".to_string())
+        );
+    }
+}
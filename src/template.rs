@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::error::ChgError;
+
+/// Minimal text template, like `- {#refs}{refs}: {/refs}{text}`.
+///
+/// `{name}` is replaced with the value, `{#name}...{/name}` is only rendered when the value is not empty.
+/// The same template also yields a regex for parsing the rendered text back.
+#[derive(Debug, Clone)]
+pub(crate) struct Template {
+    text: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Value(String),
+    Section(String, Vec<Part>),
+}
+
+impl Template {
+    /// Parses the template; only placeholders from `names` are allowed.
+    pub(crate) fn parse(text: &str, names: &[&str]) -> Result<Self, ChgError> {
+        let invalid =
+            |reason: &str| ChgError::InvalidTemplate(text.to_string(), reason.to_string());
+        // stack of open sections, with their parts so far
+        let mut stack: Vec<(String, Vec<Part>)> = vec![("".to_string(), vec![])];
+        let mut rest = text;
+        while !rest.is_empty() {
+            let (literal, tag) = match rest.find('{') {
+                None => (rest, None),
+                Some(start) => {
+                    let end = rest[start..]
+                        .find('}')
+                        .ok_or_else(|| invalid("unclosed '{'"))?;
+                    (&rest[..start], Some(&rest[start + 1..start + end]))
+                }
+            };
+            let parts = &mut stack.last_mut().unwrap().1;
+            if !literal.is_empty() {
+                parts.push(Part::Literal(literal.to_string()));
+            }
+            rest = &rest[literal.len()..];
+            let tag = match tag {
+                None => break,
+                Some(tag) => tag,
+            };
+            rest = &rest[tag.len() + 2..];
+            let name = tag.trim_start_matches(['#', '/']);
+            if !names.contains(&name) {
+                return Err(invalid(&format!(
+                    "unknown placeholder '{}', expected one of: {}",
+                    name,
+                    names.join(", ")
+                )));
+            }
+            if tag.starts_with('#') {
+                stack.push((name.to_string(), vec![]));
+            } else if tag.starts_with('/') {
+                let (open, section) = stack.pop().unwrap();
+                if open != name || stack.is_empty() {
+                    return Err(invalid(&format!("unexpected '{{/{}}}'", name)));
+                }
+                stack
+                    .last_mut()
+                    .unwrap()
+                    .1
+                    .push(Part::Section(open, section));
+            } else {
+                parts.push(Part::Value(name.to_string()));
+            }
+        }
+        if stack.len() > 1 {
+            return Err(invalid(&format!(
+                "missing '{{/{}}}'",
+                stack.last().unwrap().0
+            )));
+        }
+        Ok(Self {
+            text: text.to_string(),
+            parts: stack.pop().unwrap().1,
+        })
+    }
+
+    /// True if the value is rendered somewhere, not just tested by a section.
+    pub(crate) fn has_value(&self, name: &str) -> bool {
+        has_value(&self.parts, name)
+    }
+
+    pub(crate) fn render(&self, values: &HashMap<&str, String>) -> String {
+        let mut out = String::new();
+        render_parts(&self.parts, values, &mut out);
+        out
+    }
+
+    /// Regex matching the rendered text (trimmed), with a named group for the first occurrence of each value.
+    /// Values are matched by their `patterns`, or lazily by anything.
+    pub(crate) fn regex(&self, patterns: &HashMap<&str, &str>) -> Result<Regex, ChgError> {
+        let mut regex = String::from("^");
+        let mut seen = Vec::new();
+        regex_parts(&self.parts, patterns, &mut seen, &mut regex);
+        regex.push('$');
+        Regex::new(&regex).map_err(|e| ChgError::InvalidTemplate(self.text.clone(), e.to_string()))
+    }
+}
+
+fn has_value(parts: &[Part], name: &str) -> bool {
+    parts.iter().any(|part| match part {
+        Part::Literal(_) => false,
+        Part::Value(value) => value == name,
+        Part::Section(_, parts) => has_value(parts, name),
+    })
+}
+
+fn render_parts(parts: &[Part], values: &HashMap<&str, String>, out: &mut String) {
+    for part in parts {
+        match part {
+            Part::Literal(literal) => out.push_str(literal),
+            Part::Value(name) => out.push_str(values.get(name.as_str()).map_or("", String::as_str)),
+            Part::Section(name, parts) => {
+                if values.get(name.as_str()).is_some_and(|v| !v.is_empty()) {
+                    render_parts(parts, values, out);
+                }
+            }
+        }
+    }
+}
+
+fn regex_parts<'a>(
+    parts: &'a [Part],
+    patterns: &HashMap<&str, &str>,
+    seen: &mut Vec<&'a str>,
+    regex: &mut String,
+) {
+    for part in parts {
+        match part {
+            // rendered lines get trimmed before parsing
+            Part::Literal(literal) => {
+                let literal = if regex == "^" {
+                    literal.trim_start()
+                } else {
+                    literal.as_str()
+                };
+                regex.push_str(&regex::escape(literal))
+            }
+            Part::Value(name) => {
+                let pattern = patterns.get(name.as_str()).copied().unwrap_or(".*?");
+                if seen.contains(&name.as_str()) {
+                    regex.push_str(&format!("(?:{})", pattern));
+                } else {
+                    seen.push(name);
+                    regex.push_str(&format!("(?P<{}>{})", name, pattern));
+                }
+            }
+            Part::Section(_, parts) => {
+                regex.push_str("(?:");
+                regex_parts(parts, patterns, seen, regex);
+                regex.push_str(")?");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::template::Template;
+
+    #[test]
+    fn render_and_parse() {
+        let template = Template::parse(
+            "- {text}{#refs} ({refs}){/refs}{#authors} — {authors}{/authors}",
+            &["text", "refs", "authors"],
+        )
+        .unwrap();
+        let mut values = HashMap::new();
+        values.insert("text", "Fix NPE".to_string());
+        values.insert("refs", "#12".to_string());
+        assert_eq!(template.render(&values), "- Fix NPE (#12)");
+
+        let regex = template.regex(&HashMap::new()).unwrap();
+        let captures = regex.captures("- Fix NPE (#12) — @pk").unwrap();
+        assert_eq!(&captures["text"], "Fix NPE");
+        assert_eq!(&captures["refs"], "#12");
+        assert_eq!(&captures["authors"], "@pk");
+        let captures = regex.captures("- Fix NPE").unwrap();
+        assert_eq!(&captures["text"], "Fix NPE");
+        assert!(captures.name("refs").is_none());
+
+        assert!(Template::parse("{#text}{text}", &["text"]).is_err());
+        assert!(Template::parse("{title}", &["text"]).is_err());
+    }
+}
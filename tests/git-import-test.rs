@@ -0,0 +1,417 @@
+use std::path::PathBuf;
+
+use changelog::api::ChangeType;
+use changelog::imports::change_type::Confidence;
+use changelog::imports::from_git_repo::{GitImportOptions, Traversal};
+use changelog::{ChangeLog, ChangeLogConfig};
+use git2::{Oid, Repository, Signature, Time};
+
+/// Throw-away repository with commits on empty trees.
+struct TestRepo {
+    dir: PathBuf,
+    repo: Repository,
+    clock: i64,
+}
+
+impl TestRepo {
+    fn init(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("chg-test-{}-{}", name, std::process::id()));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        let repo = Repository::init(&dir).unwrap();
+        Self {
+            dir,
+            repo,
+            clock: 1_600_000_000,
+        }
+    }
+
+    fn commit(&mut self, parents: &[Oid], msg: &str) -> Oid {
+        self.commit_on("master", parents, msg, None)
+    }
+
+    /// Commits on given branch, keeping the first parent's tree, optionally with a file added.
+    fn commit_on(
+        &mut self,
+        branch: &str,
+        parents: &[Oid],
+        msg: &str,
+        file: Option<(&str, &str)>,
+    ) -> Oid {
+        self.clock += 3600;
+        let sig =
+            Signature::new("Petr Kozelka", "pk@example.com", &Time::new(self.clock, 0)).unwrap();
+        let parents: Vec<git2::Commit> = parents
+            .iter()
+            .map(|p| self.repo.find_commit(*p).unwrap())
+            .collect();
+        let base_tree = parents.first().map(|p| p.tree().unwrap());
+        let mut builder = self.repo.treebuilder(base_tree.as_ref()).unwrap();
+        if let Some((name, content)) = file {
+            let blob = self.repo.blob(content.as_bytes()).unwrap();
+            builder.insert(name, blob, 0o100644).unwrap();
+        }
+        let tree = self.repo.find_tree(builder.write().unwrap()).unwrap();
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        let oid = self
+            .repo
+            .commit(None, &sig, &sig, msg, &tree, &parents)
+            .unwrap();
+        let branch_ref = format!("refs/heads/{}", branch);
+        self.repo.reference(&branch_ref, oid, true, "test").unwrap();
+        if branch == "master" {
+            self.repo.set_head(&branch_ref).unwrap();
+        }
+        oid
+    }
+
+    fn tag(&self, oid: Oid, name: &str) {
+        let object = self.repo.find_object(oid, None).unwrap();
+        self.repo.tag_lightweight(name, &object, false).unwrap();
+    }
+
+    fn tag_annotated(&mut self, oid: Oid, name: &str, message: &str) {
+        self.clock += 86400;
+        let sig =
+            Signature::new("Petr Kozelka", "pk@example.com", &Time::new(self.clock, 0)).unwrap();
+        let object = self.repo.find_object(oid, None).unwrap();
+        self.repo.tag(name, &object, &sig, message, false).unwrap();
+    }
+
+    fn import_with(&self, config: &ChangeLogConfig) -> ChangeLog {
+        ChangeLog::import_git(&self.dir, &GitImportOptions::default(), config).unwrap()
+    }
+
+    fn import(&self, options: GitImportOptions) -> ChangeLog {
+        ChangeLog::import_git(&self.dir, &options, &ChangeLogConfig::default()).unwrap()
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn unreleased_texts(changelog: &ChangeLog) -> Vec<String> {
+    changelog
+        .unreleased
+        .as_ref()
+        .unwrap()
+        .items
+        .iter()
+        .map(|item| item.text.clone())
+        .collect()
+}
+
+/// Main line with a tagged release, and a merge of an unrelated history
+fn merged_history(name: &str) -> (TestRepo, Oid) {
+    let mut repo = TestRepo::init(name);
+    let a = repo.commit(&[], "Initial commit");
+    repo.tag(a, "v1.0.0");
+    let b = repo.commit(&[a], "Fix NPE (#2)");
+    let c = repo.commit(&[], "Add parser");
+    let d = repo.commit(&[c], "Fix parser (#4)");
+    let m = repo.commit(&[b, d], "Merge branch 'parser'");
+    repo.commit(&[m], "Update docs");
+    (repo, b)
+}
+
+#[test]
+fn first_parent() {
+    let (repo, _) = merged_history("first-parent");
+    let changelog = repo.import(GitImportOptions::default());
+    assert_eq!(
+        unreleased_texts(&changelog),
+        vec!["Update docs", "Merge branch 'parser'", "Fix NPE"]
+    );
+    assert_eq!(changelog.releases.len(), 1);
+    assert_eq!(changelog.releases[0].0.version, "1.0.0");
+}
+
+#[test]
+fn topological() {
+    let (repo, _) = merged_history("topological");
+    let changelog = repo.import(GitImportOptions {
+        traversal: Some(Traversal::Topological),
+        ..Default::default()
+    });
+    let mut texts = unreleased_texts(&changelog);
+    texts.sort();
+    assert_eq!(
+        texts,
+        vec!["Add parser", "Fix NPE", "Fix parser", "Update docs"]
+    );
+}
+
+#[test]
+fn merges_collapsed() {
+    let (repo, _) = merged_history("merges");
+    let changelog = repo.import(GitImportOptions {
+        traversal: Some(Traversal::MergesCollapsed),
+        ..Default::default()
+    });
+    let items = &changelog.unreleased.as_ref().unwrap().items;
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[1].text, "Merge branch 'parser'");
+    assert_eq!(items[1].refs, vec!["PR#4"]);
+}
+
+#[test]
+fn commit_range() {
+    let (repo, b) = merged_history("range");
+    let changelog = repo.import(GitImportOptions {
+        from: Some(b.to_string()),
+        ..Default::default()
+    });
+    assert_eq!(
+        unreleased_texts(&changelog),
+        vec!["Update docs", "Merge branch 'parser'"]
+    );
+    assert!(changelog.releases.is_empty());
+
+    let changelog = repo.import(GitImportOptions {
+        to: Some("v1.0.0".to_string()),
+        ..Default::default()
+    });
+    assert_eq!(changelog.releases.len(), 1);
+    assert!(changelog.unreleased.as_ref().unwrap().items.is_empty());
+}
+
+#[test]
+fn maintenance_branch() {
+    let mut repo = TestRepo::init("maintenance");
+    let a = repo.commit_on("master", &[], "Initial commit", Some(("a.txt", "a")));
+    repo.tag(a, "v1.0.0");
+    let b = repo.commit_on("master", &[a], "Add feature C", Some(("c.txt", "c")));
+    let f = repo.commit_on("master", &[b], "Fix crash (#7)", Some(("fix.txt", "fix")));
+    let g = repo.commit_on("master", &[f], "Add G", Some(("g.txt", "g")));
+    let r = repo.commit_on("master", &[g], "Release 1.1.0", None);
+    repo.tag(r, "v1.1.0");
+    let x = repo.commit_on(
+        "maint-1.0",
+        &[a],
+        "Fix crash (#7)",
+        Some(("fix.txt", "fix")),
+    );
+    let r = repo.commit_on("maint-1.0", &[x], "Release 1.0.1", None);
+    repo.tag(r, "v1.0.1");
+    let y = repo.commit_on("maint-1.0", &[r], "Backport Y", Some(("y.txt", "y")));
+    let r = repo.commit_on("maint-1.0", &[y], "Release 1.0.2", None);
+    repo.tag(r, "v1.0.2");
+
+    let changelog = repo.import(GitImportOptions::default());
+    let releases: Vec<(&str, &str, Vec<&str>)> = changelog
+        .releases
+        .iter()
+        .map(|(header, changeset)| {
+            (
+                header.version.as_str(),
+                header.branch.as_str(),
+                changeset
+                    .items
+                    .iter()
+                    .map(|item| item.text.as_str())
+                    .collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        releases,
+        vec![
+            ("1.1.0", "", vec!["Add G", "Add feature C"]),
+            ("1.0.2", "maint-1.0", vec!["Backport Y"]),
+            ("1.0.1", "maint-1.0", vec!["Fix crash"]),
+            ("1.0.0", "", vec![]),
+        ]
+    );
+
+    // branches survive a roundtrip through markdown
+    let reimported = ChangeLog::import_markdown(&changelog_text(&changelog)).unwrap();
+    let branches: Vec<&str> = reimported
+        .releases
+        .iter()
+        .map(|(header, _)| header.branch.as_str())
+        .collect();
+    assert_eq!(branches, vec!["", "maint-1.0", "maint-1.0", ""]);
+}
+
+#[test]
+fn annotated_tags() {
+    let mut repo = TestRepo::init("annotated");
+    let a = repo.commit(&[], "Initial commit");
+    repo.tag_annotated(a, "v1.0.0", "v1.0.0\n");
+    let b = repo.commit(&[a], "Fix NPE (#2)");
+    repo.tag_annotated(b, "v1.0.1", "Hotfix release.\n\nUpgrade recommended.\n");
+    let c = repo.commit(&[b], "Add parser");
+    repo.tag_annotated(
+        c,
+        "v1.1.0",
+        "Parser release\n\n[YANKED] broken on Windows\n",
+    );
+
+    let changelog = repo.import_with(&ChangeLogConfig::default());
+    let (header, changeset) = &changelog.releases[1];
+    assert_eq!(header.version, "1.0.1");
+    assert_eq!(header.timestamp.to_string(), "2020-09-15");
+    assert_eq!(changeset.notes, "Hotfix release.\n\nUpgrade recommended.");
+    assert!(changelog.releases[2].1.notes.is_empty());
+    assert!(!changelog.releases[0].0.yanked);
+
+    let config = ChangeLogConfig::parse_embedded(
+        "<!-- CHANGELOG-CONFIG\n[git]\nyanked_marker = \"[YANKED]\"\n-->",
+    )
+    .unwrap();
+    let changelog = repo.import_with(&config);
+    let (header, changeset) = &changelog.releases[0];
+    assert!(header.yanked);
+    assert_eq!(changeset.notes, "Parser release\n\nbroken on Windows");
+}
+
+#[test]
+fn commit_details() {
+    let mut repo = TestRepo::init("details");
+    let a = repo.commit(&[], "Initial commit");
+    repo.tag(a, "v1.0.0");
+    let b = repo.commit(
+        &[a],
+        "Fix NPE (#2)\n\nParser no longer fails\non empty input.\n\nCloses #1\nSigned-off-by: Petr Kozelka <pk@example.com>\n",
+    );
+    repo.commit(
+        &[b],
+        "Refactor parser\n\nChangelog: Faster parsing of large files\n",
+    );
+
+    let changelog = repo.import_with(&ChangeLogConfig::default());
+    assert_eq!(
+        unreleased_texts(&changelog),
+        vec!["Faster parsing of large files", "Fix NPE"]
+    );
+    assert!(changelog.unreleased.as_ref().unwrap().items[1]
+        .details
+        .is_empty());
+
+    let config =
+        ChangeLogConfig::parse_embedded("<!-- CHANGELOG-CONFIG\n[git]\ndetails = true\n-->")
+            .unwrap();
+    let changelog = repo.import_with(&config);
+    let items = &changelog.unreleased.as_ref().unwrap().items;
+    assert_eq!(items[0].details, "");
+    assert_eq!(items[1].details, "Parser no longer fails\non empty input.");
+    assert_eq!(items[1].refs, vec!["PR#2", "#1"]);
+
+    let mut text = Vec::new();
+    changelog.print_markdown(&mut text).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(
+        text.contains("\n  Parser no longer fails\n  on empty input.\n"),
+        "{}",
+        text
+    );
+    let reparsed = ChangeLog::import_markdown(&text).unwrap();
+    assert_eq!(
+        reparsed.unreleased.unwrap().items[1].details,
+        items[1].details
+    );
+}
+
+#[test]
+fn commit_trailers() {
+    let mut repo = TestRepo::init("trailers");
+    let a = repo.commit(&[], "Initial commit");
+    repo.tag(a, "v1.0.0");
+    let b = repo.commit(
+        &[a],
+        "Add parser\n\nChangelog-Type: fixed\nCo-authored-by: Qiang Kou <kou@example.com>\n",
+    );
+    repo.commit(&[b], "Update CI\n\nChangelog: skip\n");
+
+    let changelog = repo.import_with(&ChangeLogConfig::default());
+    let items = &changelog.unreleased.as_ref().unwrap().items;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].text, "Add parser");
+    assert_eq!(items[0].change_type, ChangeType::Fixed);
+    assert_eq!(items[0].authors, vec!["Petr Kozelka", "Qiang Kou"]);
+}
+
+#[test]
+fn release_profiles() {
+    let mut repo = TestRepo::init("profiles");
+    let a = repo.commit(&[], "Initial commit");
+    let b = repo.commit(&[a], "Add parser");
+    let c = repo.commit(&[b], "1.1.0");
+    repo.commit(&[c], "Fix NPE");
+
+    let changelog = repo.import_with(&ChangeLogConfig::default());
+    assert!(changelog.releases.is_empty());
+
+    let config = ChangeLogConfig::parse_embedded(
+        "<!-- CHANGELOG-CONFIG\n[git]\nrelease_profiles = [\"npm\"]\n-->",
+    )
+    .unwrap();
+    let changelog = repo.import_with(&config);
+    assert_eq!(unreleased_texts(&changelog), vec!["Fix NPE"]);
+    assert_eq!(changelog.releases[0].0.version, "1.1.0");
+    assert_eq!(changelog.releases[0].1.items[0].text, "Add parser");
+}
+
+#[test]
+fn guessed_types() {
+    let mut repo = TestRepo::init("guessed");
+    let a = repo.commit(&[], "Initial commit");
+    repo.tag(a, "v1.0.0");
+    let b = repo.commit(&[a], "Fix NPE");
+    repo.commit(&[b], "Parser tweaks");
+
+    let mut changelog = ChangeLog::import_markdown("# Changelog\n## 1.0.0 - 2020-09-14\n").unwrap();
+    let imported = repo.import_with(&ChangeLogConfig::default());
+    let items = &imported.unreleased.as_ref().unwrap().items;
+    assert_eq!(items[0].type_confidence, Some(Confidence::Low));
+    assert_eq!(items[1].type_confidence, Some(Confidence::Medium));
+
+    let report = changelog.sync_from(&imported).unwrap();
+    let guessed: Vec<&str> = report
+        .guessed_types()
+        .iter()
+        .map(|(_, item)| item.text.as_str())
+        .collect();
+    assert_eq!(guessed, vec!["Parser tweaks"]);
+    let reloaded = ChangeLog::import_markdown(&changelog_text(&changelog)).unwrap();
+    assert!(reloaded.unreleased.unwrap().items[0]
+        .type_confidence
+        .is_none());
+}
+
+#[test]
+fn metadata_from_prs_only() {
+    let mut repo = TestRepo::init("metadata");
+    let a = repo.commit(&[], "Initial commit");
+    repo.tag(a, "v1.0.0");
+    let b = repo.commit(&[a], "Fix crash (#7)");
+    repo.commit(&[b], "Handle empty input\n\nCloses #8");
+    std::fs::write(
+        repo.dir.join("issues.json"),
+        r#"[{"number": 7, "title": "Do not crash on empty mojo", "user": {"login": "kou"}, "pull_request": {}},
+            {"number": 8, "title": "Crash on empty input", "user": {"login": "reporter"}, "labels": [{"name": "bug"}]}]"#,
+    )
+    .unwrap();
+    let config = ChangeLogConfig::parse_embedded(
+        "<!-- CHANGELOG-CONFIG\n[metadata]\ncache_file = \"issues.json\"\n[types.labels]\nbug = \"fixed\"\n-->",
+    )
+    .unwrap();
+
+    let changelog = repo.import_with(&config);
+    let items = &changelog.unreleased.as_ref().unwrap().items;
+    assert_eq!(items[0].text, "Handle empty input");
+    assert_eq!(items[0].authors, vec!["Petr Kozelka"]);
+    assert_eq!(items[0].change_type, ChangeType::Fixed);
+    assert_eq!(items[1].text, "Do not crash on empty mojo");
+    assert_eq!(items[1].authors, vec!["@kou"]);
+}
+
+fn changelog_text(changelog: &ChangeLog) -> String {
+    let mut text = Vec::new();
+    changelog.print_markdown(&mut text).unwrap();
+    String::from_utf8(text).unwrap()
+}
@@ -1,3 +1,4 @@
+use changelog::api::SyncConflict;
 use changelog::{ChangeLog, ChgError};
 
 #[test]
@@ -52,3 +53,251 @@ fn header_garbage() {
     assert_eq!("\n# Changelog", changelog.prolog, "prolog");
 }
 
+
+const OLD: &str = r###"# Changelog
+## Unreleased
+- PR#12: manually described change / Petr Kozelka
+## 1.0.0 - 2021-01-10
+- PR#10: first release / Petr Kozelka
+"###;
+
+#[test]
+fn sync_same_release() {
+    let mut old = ChangeLog::import_markdown(OLD).unwrap();
+    let new = ChangeLog::import_markdown(r###"
+## Unreleased
+- PR#13: newer change / Qiang Kou
+- PR#12: some change / Petr Kozelka
+## 1.0.0 - 2021-01-10
+- PR#10: first release / Petr Kozelka
+"###).unwrap();
+    let report = old.sync_from(&new).unwrap();
+    assert_eq!(report.added.len(), 1, "added: {:?}", report.added);
+    assert!(report.releases_created.is_empty());
+    let unreleased = old.unreleased.unwrap();
+    assert_eq!(unreleased.items.len(), 2);
+    assert_eq!(unreleased.items[0].refs[0], "PR#13");
+    assert_eq!(unreleased.items[1].text, "manually described change");
+}
+
+#[test]
+fn sync_new_releases() {
+    let mut old = ChangeLog::import_markdown(OLD).unwrap();
+    let new = ChangeLog::import_markdown(r###"
+## Unreleased
+- PR#15: not released yet / Qiang Kou
+## 1.2.0 - 2021-03-01
+- PR#14: in second release / Qiang Kou
+- PR#10: duplicate of released item / Petr Kozelka
+## 1.1.0 - 2021-02-01
+- PR#13: newer change / Qiang Kou
+- PR#12: some change / Petr Kozelka
+## 1.0.0 - 2021-01-11
+- PR#10: first release / Petr Kozelka
+"###).unwrap();
+    let report = old.sync_from(&new).unwrap();
+    assert_eq!(report.releases_created, vec!["1.1.0", "1.2.0"]);
+    assert_eq!(report.moved.len(), 1);
+    assert_eq!(report.added.len(), 3, "added: {:?}", report.added);
+    assert_eq!(report.conflicts.len(), 2, "conflicts: {:?}", report.conflicts);
+    match &report.conflicts[0] {
+        SyncConflict::DateMismatch { version, .. } => assert_eq!(version, "1.0.0"),
+        c => panic!("Date mismatch expected - got {:?}", c),
+    }
+    match &report.conflicts[1] {
+        SyncConflict::AlreadyReleased { reference, version, .. } => {
+            assert_eq!(reference, "PR#10");
+            assert_eq!(version, "1.0.0");
+        }
+        c => panic!("Already released expected - got {:?}", c),
+    }
+    let versions: Vec<&str> = old.releases.iter().map(|(rh, _)| rh.version.as_str()).collect();
+    assert_eq!(versions, vec!["1.2.0", "1.1.0", "1.0.0"]);
+    assert_eq!(old.releases[1].1.items.len(), 2);
+    assert_eq!(old.releases[1].1.items[1].text, "manually described change");
+    assert_eq!(old.unreleased.unwrap().items.len(), 1);
+}
+
+#[test]
+fn sync_into_empty() {
+    let mut old = ChangeLog::import_markdown("# Changelog\n## Unreleased\n").unwrap();
+    let new = ChangeLog::import_markdown(OLD).unwrap();
+    let report = old.sync_from(&new).unwrap();
+    assert_eq!(report.releases_created, vec!["1.0.0"]);
+    assert_eq!(old.releases.len(), 1);
+    assert_eq!(old.unreleased.unwrap().items.len(), 1);
+}
+
+#[test]
+fn sync_missing_latest_release() {
+    let mut old = ChangeLog::import_markdown(OLD).unwrap();
+    let new = ChangeLog::import_markdown("## Unreleased\n- PR#13: newer change / Qiang Kou\n").unwrap();
+    match old.sync_from(&new) {
+        Err(ChgError::MissingLatestRelease(v)) => assert_eq!(v, "1.0.0"),
+        Err(e) => panic!("Different error expected - got {:?}", e),
+        Ok(_) => panic!("Error expected"),
+    }
+}
+
+#[test]
+fn sync_disjoint_histories() {
+    let mut old = ChangeLog::import_markdown(OLD).unwrap();
+    let new = ChangeLog::import_markdown("## 7.0.0 - 2021-01-10\n- PR#70: other project / Qiang Kou\n").unwrap();
+    match old.sync_from(&new) {
+        Err(ChgError::DisjointHistories) => {}
+        Err(e) => panic!("Different error expected - got {:?}", e),
+        Ok(_) => panic!("Error expected"),
+    }
+}
+
+#[test]
+fn sync_respects_deleted_items() {
+    let new = ChangeLog::import_markdown(r###"
+## Unreleased
+- PR#13: noise / Qiang Kou
+- PR#12: some change / Petr Kozelka
+## 1.0.0 - 2021-01-10
+- PR#10: first release / Petr Kozelka
+"###).unwrap();
+    let mut old = ChangeLog::import_markdown(OLD).unwrap();
+    let report = old.sync_from(&new).unwrap();
+    assert!(report.state_changed);
+    old.store_config().unwrap();
+
+    // maintainer deletes the noise by hand
+    let mut text = Vec::new();
+    old.print_markdown(&mut text).unwrap();
+    let text = String::from_utf8(text).unwrap().replace("- PR#13: noise / Qiang Kou\n", "");
+    let mut old = ChangeLog::import_markdown(&text).unwrap();
+    assert_eq!(old.unreleased.as_ref().unwrap().items.len(), 1);
+
+    let report = old.sync_from(&new).unwrap();
+    assert_eq!(report.newly_ignored, vec!["PR#13"]);
+    assert_eq!(report.ignored.len(), 1);
+    assert!(report.added.is_empty());
+    assert_eq!(old.unreleased.unwrap().items.len(), 1);
+}
+
+#[test]
+fn sync_twice_with_commits() {
+    let markdown = |changelog: &ChangeLog| {
+        let mut text = Vec::new();
+        changelog.print_markdown(&mut text).unwrap();
+        String::from_utf8(text).unwrap()
+    };
+    let mut new = ChangeLog::import_markdown(r###"
+## Unreleased
+- PR#5: some change / Qiang Kou
+- Refless change / Petr Kozelka
+## 1.0.0 - 2021-01-10
+- PR#10: first release / Petr Kozelka
+"###).unwrap();
+    for (i, item) in new.unreleased.as_mut().unwrap().items.iter_mut().enumerate() {
+        item.commit = Some(format!("{}{:039}", i + 1, 0));
+    }
+
+    // built-in layout does not write commits, so they are not remembered either
+    let mut old = ChangeLog::import_markdown(OLD).unwrap();
+    old.sync_from(&new).unwrap();
+    old.store_config().unwrap();
+    let text = markdown(&old);
+    assert!(!text.contains("1000000"), "{}", text);
+    let mut old = ChangeLog::import_markdown(&text).unwrap();
+    let report = old.sync_from(&new).unwrap();
+    assert!(report.newly_ignored.is_empty(), "{:?}", report.newly_ignored);
+    assert!(report.ignored.is_empty(), "{:?}", report.ignored);
+    assert!(report.added.is_empty(), "{:?}", report.added);
+    assert_eq!(old.unreleased.as_ref().unwrap().items.len(), 3);
+
+    // with commits in the layout, deleting an item ignores its commit
+    let mut old = ChangeLog::import_markdown(&format!(
+        "{}<!-- CHANGELOG-CONFIG\n[templates]\nitem = \"- {{#refs}}{{refs}}: {{/refs}}{{text}} / {{authors}}{{#commit}} {{commit}}{{/commit}}\"\n-->\n",
+        OLD
+    )).unwrap();
+    old.sync_from(&new).unwrap();
+    old.store_config().unwrap();
+    let text = markdown(&old);
+    assert!(text.contains("- Refless change / Petr Kozelka <!-- 2000000 -->\n"), "{}", text);
+    let text = text.replace("- Refless change / Petr Kozelka <!-- 2000000 -->\n", "");
+    let mut old = ChangeLog::import_markdown(&text).unwrap();
+    let report = old.sync_from(&new).unwrap();
+    assert_eq!(report.newly_ignored, vec!["2000000"]);
+    assert!(report.added.is_empty(), "{:?}", report.added);
+    assert_eq!(old.unreleased.as_ref().unwrap().items.len(), 2);
+}
+
+#[test]
+fn ignore_command() {
+    let mut old = ChangeLog::import_markdown(OLD).unwrap();
+    let removed = old.ignore("PR#12");
+    assert_eq!(removed.len(), 1);
+    let new = ChangeLog::import_markdown(OLD).unwrap();
+    let report = old.sync_from(&new).unwrap();
+    assert_eq!(report.ignored.len(), 1);
+    assert!(old.unreleased.unwrap().items.is_empty());
+}
+
+#[test]
+fn sync_matches_refless_items() {
+    let mut old = ChangeLog::import_markdown(r###"
+## Unreleased
+- Fix NPE when parsing empty mojo / Petr Kozelka
+- Upgrade test dependencies / Petr Kozelka
+- Upgrade tests dependencies / Petr Kozelka
+## 1.0.0 - 2021-01-10
+- PR#10: first release / Petr Kozelka
+"###).unwrap();
+    let mut new = ChangeLog::import_markdown(r###"
+## Unreleased
+- Brand new feature / Qiang Kou
+- fix NPE when parsing an empty MOJO / Petr Kozelka
+- Upgraded test dependencies / Petr Kozelka
+## 1.0.0 - 2021-01-10
+- PR#10: first release / Petr Kozelka
+"###).unwrap();
+    for item in &mut new.unreleased.as_mut().unwrap().items {
+        item.commit = Some(format!("{:040}", item.text.len()));
+    }
+    let report = old.sync_from(&new).unwrap();
+    assert_eq!(report.added.len(), 1, "added: {:?}", report.added);
+    assert_eq!(report.added[0].1.text, "Brand new feature");
+    assert_eq!(report.conflicts.len(), 1, "conflicts: {:?}", report.conflicts);
+    match &report.conflicts[0] {
+        SyncConflict::AmbiguousMatch { item, candidates } => {
+            assert_eq!(item.text, "Upgraded test dependencies");
+            assert_eq!(candidates.len(), 2);
+        }
+        c => panic!("Ambiguous match expected - got {:?}", c),
+    }
+
+    // added item is found by its commit id next time, even if reworded
+    let mut unreleased = old.unreleased.take().unwrap();
+    unreleased.items[0].text = "Brand new feature, manually described".to_string();
+    old.unreleased = Some(unreleased);
+    let report = old.sync_from(&new).unwrap();
+    assert!(report.added.is_empty(), "added: {:?}", report.added);
+}
+
+#[test]
+fn sync_matches_refs_by_identity() {
+    let mut old = ChangeLog::import_markdown(r###"# Changelog
+<!-- CHANGELOG-CONFIG
+[keys]
+issue_link = "https://github.com/o/r/issues/{number}"
+pr_link = "https://github.com/o/r/pull/{number}"
+-->
+## Unreleased
+- https://github.com/o/r/issues/977: described by hand / Petr Kozelka
+- !979: merged elsewhere / Qiang Kou
+"###).unwrap();
+    let new = ChangeLog::import_markdown(r###"
+## Unreleased
+- PR#979: not throw exception / Qiang Kou
+- #977: daimojo throws on import / Petr Kozelka
+"###).unwrap();
+    let report = old.sync_from(&new).unwrap();
+    assert!(report.added.is_empty(), "added: {:?}", report.added);
+    let unreleased = old.unreleased.unwrap();
+    assert_eq!(unreleased.items.len(), 2);
+    assert_eq!(unreleased.items[0].refs, vec!["#977"]);
+}